@@ -0,0 +1,369 @@
+// src/hostname.rs
+//! Host canonicalisation, shared by PAC evaluation, `NO_PROXY` and system bypass lists.
+//!
+//! Every host name is compared in the form produced by [`canonicalize_host`], so
+//! `Bücher.Example.`, `xn--bcher-kva.example` and `bücher.example` are the same host,
+//! and so are `[::1]` and `0:0:0:0:0:0:0:1`.
+
+use crate::log_debug;
+use crate::pac::net::{ip_in_prefix, parse_ip};
+use std::net::{IpAddr, Ipv4Addr};
+use url::Host;
+
+/// Canonical form of a host name or IP literal:
+///
+/// - surrounding whitespace and IPv6 brackets are removed, and IP literals are printed
+///   in their standard form (`[0:0::1]` becomes `::1`);
+/// - one trailing dot is dropped (`example.com.` becomes `example.com`);
+/// - domain names are lowercased and internationalised names converted to punycode
+///   (`Bücher.example` becomes `xn--bcher-kva.example`).
+///
+/// Names that are not valid hosts are only lowercased.
+///
+/// ```
+/// use proxyparser::hostname::canonicalize_host;
+///
+/// assert_eq!(canonicalize_host("WWW.Example.COM."), "www.example.com");
+/// assert_eq!(canonicalize_host("Bücher.example"), "xn--bcher-kva.example");
+/// assert_eq!(canonicalize_host("[2001:DB8:0::1]"), "2001:db8::1");
+/// ```
+pub fn canonicalize_host(host: &str) -> String {
+    let host = host.trim();
+    if let Some(ip) = parse_ip(host) {
+        return ip.to_string();
+    }
+    let host = match host.strip_suffix('.') {
+        Some(stripped) if !stripped.is_empty() => stripped,
+        _ => host,
+    };
+    match Host::parse(host) {
+        Ok(Host::Domain(domain)) => domain,
+        Ok(Host::Ipv4(ip)) => ip.to_string(),
+        Ok(Host::Ipv6(ip)) => ip.to_string(),
+        Err(_) => host.to_lowercase(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BypassEntry {
+    /// `*`: every host.
+    All,
+    /// `<local>`: host names without a dot.
+    Local,
+    /// `example.com`, `.example.com` or `*.example.com`: the domain and its subdomains.
+    Domain(String),
+    /// An IP address or a `10.0.0.0/8`-style network; `10.*` and `192.168.1.*` are
+    /// stored as networks too.
+    Network(String),
+    /// Any other entry containing `*`, such as `*.corp*`, matched as a glob against the
+    /// whole host.
+    Pattern(String),
+}
+
+/// A proxy bypass list such as `NO_PROXY`, GNOME `ignore-hosts`, macOS `ExceptionsList`
+/// or the Windows `ProxyOverride` value.
+///
+/// Entries are separated by commas, semicolons or whitespace. An entry is one of:
+///
+/// - `*`, bypassing every host;
+/// - `<local>`, bypassing plain host names (no dot);
+/// - a domain, with or without a leading `.` or `*.`, bypassing the domain and all its
+///   subdomains;
+/// - an IP address or CIDR network (`192.168.0.0/16`, `fd00::/8`), or an IPv4 prefix
+///   followed by `*` octets (`10.*`, `192.168.*`, `192.168.1.*`);
+/// - any other pattern with `*` wildcards (`*.corp*`, `build-*.example`), where `*`
+///   matches any run of characters, dots included.
+///
+/// A `:port` suffix on an entry is ignored, and so are networks with an invalid prefix
+/// length. Hosts and entries are compared after [`canonicalize_host`]; glob patterns
+/// are only lowercased.
+///
+/// ```
+/// use proxyparser::hostname::BypassList;
+///
+/// let list = BypassList::parse("localhost, .corp.example; 10.0.0.0/8 <local> 192.168.* *.lab*");
+/// assert!(list.matches("WIKI.Corp.Example."));
+/// assert!(list.matches("10.1.2.3"));
+/// assert!(list.matches("intranet"));
+/// assert!(list.matches("192.168.40.1"));
+/// assert!(list.matches("build.lab7.example"));
+/// assert!(!list.matches("example.com"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BypassList {
+    entries: Vec<BypassEntry>,
+}
+
+impl BypassList {
+    pub fn parse(list: &str) -> Self {
+        let entries = list
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|entry| !entry.is_empty())
+            .filter_map(parse_entry)
+            .collect();
+        BypassList { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `host` (a host name or IP literal, not a URL) should bypass the proxy.
+    pub fn matches(&self, host: &str) -> bool {
+        let host = canonicalize_host(host);
+        let ip = parse_ip(&host);
+        self.entries.iter().any(|entry| match entry {
+            BypassEntry::All => true,
+            BypassEntry::Local => ip.is_none() && !host.contains('.'),
+            BypassEntry::Domain(domain) => {
+                host == *domain
+                    || (host.len() > domain.len()
+                        && host.ends_with(domain.as_str())
+                        && host.as_bytes()[host.len() - domain.len() - 1] == b'.')
+            }
+            BypassEntry::Network(network) => ip.is_some_and(|ip| ip_in_network(ip, network)),
+            BypassEntry::Pattern(pattern) => glob_match(pattern, &host),
+        })
+    }
+}
+
+fn parse_entry(entry: &str) -> Option<BypassEntry> {
+    if entry == "*" {
+        return Some(BypassEntry::All);
+    }
+    if entry.eq_ignore_ascii_case("<local>") {
+        return Some(BypassEntry::Local);
+    }
+    if let Some((ip, len)) = entry.split_once('/') {
+        if let Some(ip) = parse_ip(ip) {
+            let network = format!("{}/{}", ip, len);
+            if ip_in_prefix(ip, &network).is_none() {
+                log_debug!("Ignoring bypass entry {:?}: invalid prefix length", entry);
+                return None;
+            }
+            return Some(BypassEntry::Network(network));
+        }
+    }
+    let host = strip_port(entry);
+    if let Some(ip) = parse_ip(host) {
+        return Some(BypassEntry::Network(ip.to_string()));
+    }
+    if let Some(network) = ipv4_wildcard(host) {
+        return Some(BypassEntry::Network(network));
+    }
+    let domain = host
+        .strip_prefix("*.")
+        .or_else(|| host.strip_prefix('.'))
+        .unwrap_or(host);
+    if domain.contains('*') {
+        let pattern = host.strip_suffix('.').unwrap_or(host);
+        return Some(BypassEntry::Pattern(pattern.to_lowercase()));
+    }
+    Some(BypassEntry::Domain(canonicalize_host(domain)))
+}
+
+/// `10.*`, `192.168.*.*` or `192.168.1.*` -> `10.0.0.0/8`, `192.168.0.0/16`, ...
+fn ipv4_wildcard(entry: &str) -> Option<String> {
+    let parts: Vec<&str> = entry.split('.').collect();
+    let fixed = parts.iter().take_while(|part| **part != "*").count();
+    if parts.len() > 4 || fixed == 0 || parts[fixed..].iter().any(|part| *part != "*") {
+        return None;
+    }
+    let mut octets = [0u8; 4];
+    for (octet, part) in octets.iter_mut().zip(&parts[..fixed]) {
+        if !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *octet = part.parse().ok()?;
+    }
+    // fixed == parts.len() 时没有通配符，不是此形式
+    (fixed < parts.len()).then(|| format!("{}/{}", Ipv4Addr::from(octets), fixed * 8))
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 '*' 的位置及其当前匹配到的文本位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if pattern.get(p) == Some(&b'*') {
+            star = Some((p, t));
+            p += 1;
+        } else if pattern.get(p) == Some(&text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            star = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// `host:8080` -> `host`, `[::1]:8080` -> `[::1]`; a bare IPv6 literal is left alone.
+fn strip_port(entry: &str) -> &str {
+    if entry.starts_with('[') {
+        return entry.find(']').map_or(entry, |end| &entry[..=end]);
+    }
+    match entry.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') && port.chars().all(|c| c.is_ascii_digit()) => {
+            host
+        }
+        _ => entry,
+    }
+}
+
+fn ip_in_network(ip: IpAddr, network: &str) -> bool {
+    if network.contains('/') {
+        ip_in_prefix(ip, network).unwrap_or(false)
+    } else {
+        parse_ip(network) == Some(ip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bypasses(list: &str, host: &str) -> bool {
+        BypassList::parse(list).matches(host)
+    }
+
+    #[test]
+    fn canonical_hosts() {
+        assert_eq!(canonicalize_host("Bücher.Example"), "xn--bcher-kva.example");
+        assert_eq!(
+            canonicalize_host("xn--bcher-kva.example"),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(canonicalize_host("ÉCOLE.fr."), "xn--cole-9oa.fr");
+        assert_eq!(canonicalize_host("[::1]"), "::1");
+        assert_eq!(canonicalize_host("[0:0:0:0:0:0:0:1]"), "::1");
+        assert_eq!(canonicalize_host("2001:DB8::1"), "2001:db8::1");
+        assert_eq!(canonicalize_host(" Example.COM. "), "example.com");
+        // 只去掉一个末尾的点
+        assert_eq!(canonicalize_host("example.com.."), "example.com.");
+        assert_eq!(canonicalize_host("."), ".");
+        assert_eq!(canonicalize_host("127.0.0.1"), "127.0.0.1");
+        // url 按 WHATWG 规则解析数字形式的 IPv4
+        assert_eq!(canonicalize_host("0x7f.1"), "127.0.0.1");
+        // 非法主机名只转小写
+        assert_eq!(canonicalize_host("Bad Host"), "bad host");
+    }
+
+    #[test]
+    fn ports_are_ignored() {
+        assert_eq!(strip_port("proxy.corp:8080"), "proxy.corp");
+        assert_eq!(strip_port("[::1]:8080"), "[::1]");
+        assert_eq!(strip_port("[fd00::1]"), "[fd00::1]");
+        assert_eq!(strip_port("fd00::1"), "fd00::1");
+        assert_eq!(strip_port("host:http"), "host:http");
+        assert!(bypasses("intranet.corp:8080", "intranet.corp"));
+        assert!(bypasses("10.1.2.3:3128", "10.1.2.3"));
+        assert!(bypasses("[::1]:8080", "::1"));
+        assert!(bypasses("[::1]", "[0::1]"));
+        assert!(bypasses("::1", "[::1]"));
+    }
+
+    #[test]
+    fn domains() {
+        for entry in [
+            "corp.example",
+            ".corp.example",
+            "*.corp.example",
+            "CORP.Example.",
+        ] {
+            assert!(bypasses(entry, "corp.example"), "{}", entry);
+            assert!(bypasses(entry, "wiki.corp.example."), "{}", entry);
+            assert!(!bypasses(entry, "notcorp.example"), "{}", entry);
+            assert!(!bypasses(entry, "corp.example.org"), "{}", entry);
+        }
+        assert!(bypasses("bücher.example", "xn--bcher-kva.example"));
+        assert!(bypasses("xn--bcher-kva.example", "WWW.Bücher.example"));
+    }
+
+    #[test]
+    fn networks() {
+        assert!(bypasses("192.168.0.0/16", "192.168.4.5"));
+        assert!(!bypasses("192.168.0.0/16", "192.169.0.1"));
+        assert!(bypasses("fd00::/8", "[fd12::1]"));
+        assert!(!bypasses("fd00::/8", "10.0.0.1"));
+        // 非法的前缀长度被忽略，而不是匹配所有地址
+        for entry in ["10.0.0.0/33", "10.0.0.0/x", "10.0.0.0/", "fd00::/129"] {
+            assert!(BypassList::parse(entry).is_empty(), "{}", entry);
+        }
+        let list = BypassList::parse("10.0.0.0/40, 10.0.0.0/8");
+        assert_eq!(list.entries.len(), 1);
+        assert!(list.matches("10.9.9.9"));
+        // IP 条目不匹配主机名
+        assert!(!bypasses("127.0.0.1", "localhost"));
+    }
+
+    #[test]
+    fn local_and_all() {
+        assert!(bypasses("<local>", "intranet"));
+        assert!(bypasses("<LOCAL>", "Intranet."));
+        assert!(!bypasses("<local>", "wiki.corp"));
+        assert!(!bypasses("<local>", "10.0.0.1"));
+        assert!(!bypasses("<local>", "::1"));
+        assert!(bypasses("*", "anything.example"));
+        assert!(bypasses("*", "::1"));
+        assert!(!bypasses("", "anything.example"));
+        assert_eq!(
+            BypassList::parse(" <local> ;; , *").entries,
+            [BypassEntry::Local, BypassEntry::All]
+        );
+    }
+
+    #[test]
+    fn partial_ipv4_wildcards() {
+        assert_eq!(
+            BypassList::parse("10.* 192.168.* 172.16.5.* 192.168.*.*").entries,
+            [
+                BypassEntry::Network("10.0.0.0/8".to_string()),
+                BypassEntry::Network("192.168.0.0/16".to_string()),
+                BypassEntry::Network("172.16.5.0/24".to_string()),
+                BypassEntry::Network("192.168.0.0/16".to_string()),
+            ]
+        );
+        assert!(bypasses("10.*", "10.200.3.4"));
+        assert!(!bypasses("10.*", "11.0.0.1"));
+        // 只匹配 IP 地址，不匹配以 "10." 开头的主机名
+        assert!(!bypasses("10.*", "10.example.com"));
+        assert!(bypasses("192.168.1.*", "192.168.1.254"));
+        assert!(!bypasses("192.168.1.*", "192.168.2.1"));
+        assert!(!bypasses("192.168.*", "fd00::1"));
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(bypasses("*.corp*", "wiki.corp.example"));
+        assert!(bypasses("*.corp*", "a.b.corporate.example"));
+        assert!(!bypasses("*.corp*", "corp.example"));
+        assert!(bypasses("build-*.example", "BUILD-42.example"));
+        assert!(!bypasses("build-*.example", "build-42.example.org"));
+        assert!(bypasses("*example.com", "example.com"));
+        assert!(bypasses("*example.com", "myexample.com"));
+        assert!(bypasses("db*.internal.", "db01.internal"));
+        assert!(bypasses("*.*", "a.b"));
+        assert!(!bypasses("*.*", "intranet"));
+        // 前导 "*." 加普通域名仍是域名条目，包括域名本身
+        assert_eq!(
+            BypassList::parse("*.corp.example").entries,
+            [BypassEntry::Domain("corp.example".to_string())]
+        );
+    }
+
+    #[test]
+    fn glob_backtracking() {
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(glob_match("a**b", "ab"));
+        assert!(!glob_match("*a*b", "xaxxa"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("", "a"));
+    }
+}
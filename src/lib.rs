@@ -1,10 +1,14 @@
 // src/lib.rs
 pub mod env_proxy;
+pub mod hostname;
 pub mod log;
 pub mod pac;
+pub mod proxy;
 pub mod system_proxy;
 use std::error::Error;
 
+pub use proxy::{Credentials, ParseProxyError, ProxyDescriptor, ProxyServer};
+
 /// Detects the appropriate proxy for the given URL, following standard priority order:
 ///
 /// 1. Environment variables (HTTP_PROXY, etc.)
@@ -15,36 +19,68 @@ use std::error::Error;
 ///
 /// # Examples
 ///
+/// ```no_run
+/// # use proxyparser::{find_proxy_for_url, ProxyDescriptor};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// match find_proxy_for_url("https://httpbin.org/ip")? {
+///     ProxyDescriptor::Direct => println!("DIRECT"),
+///     proxy => println!("{}", proxy), // e.g. http://host:port
+/// }
+/// # Ok(())
+/// # }
 /// ```
-/// let proxy = find_proxy_for_url("https://httpbin.org/ip")?;
-/// println!("{}", proxy); // DIRECT or PROXY host:port
-/// ```
-pub fn find_proxy_for_url(url: &str) -> Result<String, Box<dyn Error>> {
+pub fn find_proxy_for_url(url: &str) -> Result<ProxyDescriptor, Box<dyn Error>> {
     // 1. 环境变量最高优先级
     if let Some(proxy) = env_proxy::get_env_proxy(url) {
         log_info!("Using environment proxy: {}", proxy);
         return Ok(proxy);
     }
 
-    // 2. 系统代理（macOS）
-    if let Some(system_result) = system_proxy::get_system_proxy(url) {
-        if pac::is_pac_url(&system_result) {
-            log_info!("Detected PAC configuration: {}", system_result);
-            match pac::evaluate_pac_for_url(&system_result, url) {
-                Ok(proxy) => {
-                    log_info!("PAC resolved proxy: {}", proxy);
-                    return Ok(proxy);
-                }
-                Err(e) => {
-                    log_warn!("PAC evaluation failed: {}. Falling back to DIRECT", e);
-                }
-            }
-        } else {
-            log_info!("Using system manual proxy: {}", system_result);
-            return Ok(system_result);
-        }
+    // 2. 系统代理（手动代理或 PAC）
+    if let Some(proxy) = system_proxy::get_system_proxy(url) {
+        log_info!("Using system proxy: {}", proxy);
+        return Ok(proxy);
+    }
+
+    log_info!("No proxy found, using DIRECT");
+    Ok(ProxyDescriptor::Direct)
+}
+
+/// Same detection order as [`find_proxy_for_url`], but returns the full ordered
+/// fallback chain, keeping `DIRECT` where it appears.
+///
+/// Environment and manual system proxies yield a single-entry chain; a PAC script
+/// returning `PROXY a:8080; PROXY b:8080; DIRECT` yields all three entries in order.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use proxyparser::find_proxy_chain_for_url;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// for proxy in find_proxy_chain_for_url("https://httpbin.org/ip")? {
+///     println!("{}", proxy); // try each in order until one works
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_proxy_chain_for_url(url: &str) -> Result<Vec<ProxyDescriptor>, Box<dyn Error>> {
+    if let Some(proxy) = env_proxy::get_env_proxy(url) {
+        log_info!("Using environment proxy: {}", proxy);
+        return Ok(vec![proxy]);
+    }
+
+    if let Some(chain) = system_proxy::get_system_proxy_chain(url) {
+        log_info!(
+            "Using system proxy chain: {}",
+            chain
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+        return Ok(chain);
     }
 
     log_info!("No proxy found, using DIRECT");
-    Ok("DIRECT".to_string())
+    Ok(vec![ProxyDescriptor::Direct])
 }
@@ -1,16 +1,118 @@
 // src/main.rs
 use proxyparser::find_proxy_for_url;
+use proxyparser::pac::{
+    DownloaderConfig, PacCache, PacCacheConfig, PacEngine, PacFetchRoute, PacOptions, PacSource,
+};
+use proxyparser::system_proxy::get_system_proxy_config;
 use std::env;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: proxyparser [OPTIONS] [URL]
+
+Prints the proxy to use for URL (default: https://www.google.com).
+
+PAC options:
+  --pac <LOCATION>          evaluate this PAC instead of detecting the proxy: an
+                            http(s)://, file:// or data: URL, or a local path
+  --pac-script <SCRIPT>     evaluate this PAC script text instead of detecting
+
+PAC download options:
+  --connect-timeout <SECS>  connection timeout (default 5)
+  --timeout <SECS>          timeout for each whole download attempt (default 15)
+  --retries <N>             retries of transient failures (default 2)
+  --max-pac-size <BYTES>    largest accepted PAC script (default 8388608)
+  --ca-bundle <PATH>        extra trusted root certificates (PEM); repeatable
+  --user-agent <UA>         User-Agent header for PAC downloads
+  --pac-via <ROUTE>         how to reach the PAC server: direct (default), env,
+                            or an HTTP(S) proxy URL such as http://proxy.corp:3128
+
+Other options:
+  --system-config           print the system proxy settings and exit
+  -h, --help                show this help";
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let url = args
-        .get(1)
-        .map(|s| s.as_str())
-        .unwrap_or("https://www.google.com");
+    let mut url = None;
+    let mut downloader = DownloaderConfig::default();
+    let mut pac_source = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next().unwrap_or_else(|| {
+                eprintln!("Error: {} needs a value\n\n{}", arg, USAGE);
+                process::exit(2);
+            })
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--system-config" => {
+                println!("System proxy configuration: {}", get_system_proxy_config());
+                return;
+            }
+            "--connect-timeout" => downloader.connect_timeout = parse_secs(&arg, &value()),
+            "--timeout" => downloader.request_timeout = parse_secs(&arg, &value()),
+            "--retries" => downloader.retries = parse_value(&arg, &value()),
+            "--max-pac-size" => downloader.max_body_bytes = parse_value(&arg, &value()),
+            "--ca-bundle" => downloader.extra_root_certificates.push(value().into()),
+            "--user-agent" => downloader.user_agent = value(),
+            "--pac-via" => downloader.route = parse_route(&value()),
+            "--pac" => {
+                let location = value();
+                pac_source = Some(PacSource::from_location(&location).unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    process::exit(2);
+                }));
+            }
+            "--pac-script" => pac_source = Some(PacSource::Script(value())),
+            _ if arg.starts_with('-') => {
+                eprintln!("Error: unknown option {}\n\n{}", arg, USAGE);
+                process::exit(2);
+            }
+            _ => url = Some(arg),
+        }
+    }
+    let url = url.unwrap_or_else(|| "https://www.google.com".to_string());
+
+    // main 开头安装，之后的 PAC 下载都使用这份配置
+    PacCache::install_shared(PacCache::new(PacCacheConfig {
+        downloader,
+        ..PacCacheConfig::default()
+    }));
 
-    match find_proxy_for_url(url) {
+    let result = match &pac_source {
+        Some(source) => PacEngine::from_source(source, &PacCache::shared(), PacOptions::default())
+            .and_then(|mut engine| engine.find_proxy(&url)),
+        None => find_proxy_for_url(&url),
+    };
+    match result {
         Ok(proxy) => println!("Proxy for {} → {}", url, proxy),
         Err(e) => eprintln!("Error: {}", e),
     }
 }
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Error: invalid value '{}' for {}", value, option);
+        process::exit(2);
+    })
+}
+
+fn parse_secs(option: &str, value: &str) -> Duration {
+    Duration::try_from_secs_f64(parse_value(option, value)).unwrap_or_else(|_| {
+        eprintln!("Error: invalid value '{}' for {}", value, option);
+        process::exit(2);
+    })
+}
+
+fn parse_route(value: &str) -> PacFetchRoute {
+    match value {
+        "direct" => PacFetchRoute::Direct,
+        "env" => PacFetchRoute::Environment,
+        proxy => PacFetchRoute::Proxy(parse_value("--pac-via", proxy)),
+    }
+}
@@ -0,0 +1,688 @@
+// src/pac/cache.rs
+use super::decode::decode_pac;
+use super::downloader::{fetch_pac, CacheHeaders, DownloaderConfig, FetchOutcome, Validators};
+use super::source::{decode_data_url, PacSource};
+use crate::{log_debug, log_warn};
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Settings for a [`PacCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacCacheConfig {
+    /// Freshness lifetime of a script whose response carries no `Cache-Control: max-age`,
+    /// `Expires` or `Last-Modified` header.
+    pub default_ttl: Duration,
+    /// Upper bound on any freshness lifetime, however long the server allows.
+    pub max_ttl: Duration,
+    /// After a failed refresh, how long the last known good script is served before the
+    /// server is tried again, so an outage does not cost a full download per lookup.
+    pub failure_backoff: Duration,
+    /// Directory for the on-disk cache; `None` keeps scripts in memory only.
+    pub disk_dir: Option<PathBuf>,
+    /// How scripts are downloaded.
+    pub downloader: DownloaderConfig,
+}
+
+impl Default for PacCacheConfig {
+    fn default() -> Self {
+        PacCacheConfig {
+            default_ttl: Duration::from_secs(5 * 60),
+            max_ttl: Duration::from_secs(24 * 60 * 60),
+            failure_backoff: Duration::from_secs(60),
+            disk_dir: None,
+            downloader: DownloaderConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedScript {
+    script: String,
+    validators: Validators,
+    fetched: SystemTime,
+    fresh_until: SystemTime,
+    /// `Cache-Control: no-store`: kept in memory as last known good, never written to disk.
+    no_store: bool,
+}
+
+/// A cache of downloaded PAC scripts.
+///
+/// A cached script is used without contacting the server while it is fresh, as given by
+/// `Cache-Control: max-age`, `no-cache`, `Expires` or (heuristically) `Last-Modified`.
+/// A stale script is revalidated with `If-None-Match`/`If-Modified-Since`, so an
+/// unchanged script costs a `304 Not Modified`.
+///
+/// When a refresh fails (server unreachable, HTTP error), the last known good script is
+/// served and a warning is logged, instead of losing proxy routing altogether. It is then
+/// served without retrying for [`PacCacheConfig::failure_backoff`].
+///
+/// With [`PacCacheConfig::disk_dir`] set, scripts also survive restarts. Scripts served
+/// with `Cache-Control: no-store` are never written to disk.
+///
+/// Clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct PacCache {
+    config: PacCacheConfig,
+    entries: Arc<Mutex<HashMap<String, CachedScript>>>,
+}
+
+static SHARED: OnceLock<PacCache> = OnceLock::new();
+
+impl PacCache {
+    pub fn new(config: PacCacheConfig) -> Self {
+        PacCache {
+            config,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The process-wide cache used by [`PacEngine::from_url`](super::PacEngine::from_url)
+    /// and the `find_proxy_*` functions. In-memory only unless
+    /// [`install_shared`](Self::install_shared) was called first.
+    pub fn shared() -> PacCache {
+        SHARED
+            .get_or_init(|| PacCache::new(PacCacheConfig::default()))
+            .clone()
+    }
+
+    /// Makes `cache` the process-wide cache, e.g. to enable the disk cache or configure
+    /// the downloader. Returns `false`, changing nothing, once [`shared`](Self::shared)
+    /// has been used.
+    pub fn install_shared(cache: PacCache) -> bool {
+        SHARED.set(cache).is_ok()
+    }
+
+    pub fn config(&self) -> &PacCacheConfig {
+        &self.config
+    }
+
+    /// The PAC script from `source`.
+    ///
+    /// URLs go through [`fetch`](Self::fetch). Files are read on every call, falling back
+    /// to the last successfully read copy (with a warning) when the file becomes
+    /// unreadable; they are never copied to the disk cache. `data:` URLs and inline
+    /// scripts are used as is.
+    pub fn load(&self, source: &PacSource) -> Result<String, Box<dyn std::error::Error>> {
+        match source {
+            PacSource::Url(url) => self.fetch(url),
+            PacSource::File(path) => {
+                let key = source.key();
+                match fs::read(path) {
+                    Ok(bytes) => {
+                        let script = decode_pac(&bytes, None, &key)?;
+                        log_debug!("Read PAC script from {}", path.display());
+                        self.remember(&key, &script);
+                        Ok(script)
+                    }
+                    Err(e) => match self.lock().get(&key) {
+                        Some(entry) => {
+                            log_warn!(
+                                "Failed to read PAC file {}: {}; using last known good copy",
+                                path.display(),
+                                e
+                            );
+                            Ok(entry.script.clone())
+                        }
+                        None => {
+                            Err(format!("cannot read PAC file {}: {}", path.display(), e).into())
+                        }
+                    },
+                }
+            }
+            PacSource::Data(data_url) => {
+                let (bytes, media_type) = decode_data_url(data_url)?;
+                Ok(decode_pac(&bytes, Some(&media_type), &source.key())?)
+            }
+            PacSource::Script(script) => Ok(script.trim_start_matches('\u{feff}').to_string()),
+        }
+    }
+
+    /// The PAC script at `pac_url`, from the cache when fresh, otherwise downloaded or
+    /// revalidated.
+    pub fn fetch(&self, pac_url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let now = SystemTime::now();
+        let cached = self.get(pac_url);
+        if let Some(entry) = &cached {
+            if entry.fresh_until > now {
+                log_debug!("Using cached PAC script for {}", pac_url);
+                return Ok(entry.script.clone());
+            }
+        }
+
+        let validators = cached
+            .as_ref()
+            .map(|entry| entry.validators.clone())
+            .unwrap_or_default();
+        let outcome = fetch_pac(pac_url, &validators, &self.config.downloader);
+        self.update(pac_url, cached, outcome, now)
+    }
+
+    /// Applies the result of a download to the cache entry `cached` of `pac_url`.
+    fn update(
+        &self,
+        pac_url: &str,
+        cached: Option<CachedScript>,
+        outcome: Result<FetchOutcome, Box<dyn std::error::Error>>,
+        now: SystemTime,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        match outcome {
+            Ok(FetchOutcome::Modified { script, headers }) => {
+                self.store(pac_url, script.clone(), &headers, now);
+                Ok(script)
+            }
+            Ok(FetchOutcome::NotModified { mut headers }) => match cached {
+                Some(entry) => {
+                    // 304 可以省略校验器，沿用缓存中的值
+                    let validators = &mut headers.validators;
+                    validators.etag = validators.etag.take().or(entry.validators.etag);
+                    validators.last_modified = validators
+                        .last_modified
+                        .take()
+                        .or(entry.validators.last_modified);
+                    self.store(pac_url, entry.script.clone(), &headers, now);
+                    Ok(entry.script)
+                }
+                None => Err("unexpected HTTP 304 Not Modified".into()),
+            },
+            Err(e) => match cached {
+                Some(mut entry) => {
+                    let age = now.duration_since(entry.fetched).unwrap_or_default();
+                    log_warn!(
+                        "Failed to refresh PAC script {}: {}; using last known good copy from {}s ago",
+                        pac_url,
+                        e,
+                        age.as_secs()
+                    );
+                    // 退避期内不再重试下载；只改内存中的条目，磁盘上保留服务器给出的新鲜期
+                    entry.fresh_until = now + self.config.failure_backoff;
+                    let script = entry.script.clone();
+                    self.lock().insert(pac_url.to_string(), entry);
+                    Ok(script)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Drops every cached script, in memory and on disk.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        if let Some(dir) = &self.config.disk_dir {
+            if let Ok(files) = fs::read_dir(dir) {
+                for file in files.flatten() {
+                    if file.path().extension().is_some_and(|ext| ext == "pac") {
+                        let _ = fs::remove_file(file.path());
+                    }
+                }
+            }
+        }
+        entries.clear();
+    }
+
+    fn get(&self, pac_url: &str) -> Option<CachedScript> {
+        if let Some(entry) = self.lock().get(pac_url) {
+            return Some(entry.clone());
+        }
+        let dir = self.config.disk_dir.as_ref()?;
+        let entry = read_disk_entry(&disk_path(dir, pac_url), pac_url)?;
+        self.lock().insert(pac_url.to_string(), entry.clone());
+        Some(entry)
+    }
+
+    /// Keeps a script in memory only, as the last known good copy of `key`.
+    fn remember(&self, key: &str, script: &str) {
+        let now = SystemTime::now();
+        self.lock().insert(
+            key.to_string(),
+            CachedScript {
+                script: script.to_string(),
+                validators: Validators::default(),
+                fetched: now,
+                fresh_until: now,
+                no_store: true,
+            },
+        );
+    }
+
+    fn store(&self, pac_url: &str, script: String, headers: &CacheHeaders, now: SystemTime) {
+        let directives = CacheDirectives::parse(headers.cache_control.as_deref());
+        let ttl = self.freshness(&directives, headers, now);
+        let entry = CachedScript {
+            script,
+            validators: headers.validators.clone(),
+            fetched: now,
+            fresh_until: now + ttl,
+            no_store: directives.no_store,
+        };
+
+        if let Some(dir) = &self.config.disk_dir {
+            let path = disk_path(dir, pac_url);
+            if entry.no_store {
+                let _ = fs::remove_file(&path);
+            } else if let Err(e) = write_disk_entry(dir, &path, pac_url, &entry) {
+                log_warn!("Failed to write PAC cache file {}: {}", path.display(), e);
+            }
+        }
+        self.lock().insert(pac_url.to_string(), entry);
+    }
+
+    /// Freshness lifetime per RFC 9111: `no-cache` > `max-age` > `Expires` > 10% of the
+    /// time since `Last-Modified` > the configured default, capped at `max_ttl`.
+    fn freshness(
+        &self,
+        directives: &CacheDirectives,
+        headers: &CacheHeaders,
+        now: SystemTime,
+    ) -> Duration {
+        let ttl = if directives.no_cache || directives.no_store {
+            Duration::ZERO
+        } else if let Some(max_age) = directives.max_age {
+            max_age
+        } else if let Some(expires) = headers.expires.as_deref() {
+            // 无法解析的 Expires 视为已过期（RFC 9111 §5.3）
+            http_date(expires)
+                .and_then(|expires| expires.duration_since(now).ok())
+                .unwrap_or_default()
+        } else if let Some(last_modified) = headers
+            .validators
+            .last_modified
+            .as_deref()
+            .and_then(http_date)
+        {
+            now.duration_since(last_modified).unwrap_or_default() / 10
+        } else {
+            self.config.default_ttl
+        };
+        ttl.min(self.config.max_ttl)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CachedScript>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheDirectives {
+    no_cache: bool,
+    no_store: bool,
+    max_age: Option<Duration>,
+}
+
+impl CacheDirectives {
+    fn parse(cache_control: Option<&str>) -> Self {
+        let mut directives = CacheDirectives::default();
+        for directive in cache_control.unwrap_or_default().split(',') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "no-cache" => directives.no_cache = true,
+                "no-store" => directives.no_store = true,
+                "max-age" => {
+                    directives.max_age = value
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs)
+                }
+                _ => {}
+            }
+        }
+        directives
+    }
+}
+
+fn http_date(value: &str) -> Option<SystemTime> {
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let secs = u64::try_from(date.timestamp()).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// 用 URL 的 FNV-1a 哈希作文件名，避免 URL 中的特殊字符和长度问题
+fn disk_path(dir: &Path, pac_url: &str) -> PathBuf {
+    let hash = pac_url.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    dir.join(format!("{:016x}.pac", hash))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 磁盘格式：若干 `key: value` 头部行，一个空行，然后是脚本原文
+fn write_disk_entry(
+    dir: &Path,
+    path: &Path,
+    pac_url: &str,
+    entry: &CachedScript,
+) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut contents = format!(
+        "url: {}\nfetched: {}\nfresh-until: {}\n",
+        pac_url,
+        unix_secs(entry.fetched),
+        unix_secs(entry.fresh_until)
+    );
+    if let Some(etag) = &entry.validators.etag {
+        contents.push_str(&format!("etag: {}\n", etag));
+    }
+    if let Some(last_modified) = &entry.validators.last_modified {
+        contents.push_str(&format!("last-modified: {}\n", last_modified));
+    }
+    contents.push('\n');
+    contents.push_str(&entry.script);
+
+    // 先写临时文件再改名，避免留下写了一半的缓存
+    let tmp = path.with_extension("pac.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+fn read_disk_entry(path: &Path, pac_url: &str) -> Option<CachedScript> {
+    let contents = fs::read_to_string(path).ok()?;
+    let (header, script) = contents.split_once("\n\n")?;
+
+    let mut url = None;
+    let mut fetched = None;
+    let mut fresh_until = None;
+    let mut validators = Validators::default();
+    for line in header.lines() {
+        let (key, value) = line.split_once(": ")?;
+        match key {
+            "url" => url = Some(value),
+            "fetched" => fetched = value.parse::<u64>().ok(),
+            "fresh-until" => fresh_until = value.parse::<u64>().ok(),
+            "etag" => validators.etag = Some(value.to_string()),
+            "last-modified" => validators.last_modified = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    // 哈希冲突时文件属于另一个 URL
+    if url != Some(pac_url) {
+        return None;
+    }
+    log_debug!("Loaded PAC script for {} from {}", pac_url, path.display());
+    Some(CachedScript {
+        script: script.to_string(),
+        validators,
+        fetched: UNIX_EPOCH + Duration::from_secs(fetched?),
+        fresh_until: UNIX_EPOCH + Duration::from_secs(fresh_until?),
+        no_store: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "http://wpad.corp/proxy.pac";
+
+    /// A whole number of seconds, so that times survive the disk format.
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_792_000_000)
+    }
+
+    fn headers(cache_control: Option<&str>, expires: Option<&str>) -> CacheHeaders {
+        CacheHeaders {
+            cache_control: cache_control.map(String::from),
+            expires: expires.map(String::from),
+            validators: Validators::default(),
+        }
+    }
+
+    fn validators(etag: Option<&str>, last_modified: Option<&str>) -> Validators {
+        Validators {
+            etag: etag.map(String::from),
+            last_modified: last_modified.map(String::from),
+        }
+    }
+
+    fn http_date_at(time: SystemTime) -> String {
+        DateTime::<chrono::Utc>::from(time).to_rfc2822()
+    }
+
+    /// An empty directory under the system temp dir, unique to this test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("proxyparser-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn disk_cache(dir: &Path) -> PacCache {
+        PacCache::new(PacCacheConfig {
+            disk_dir: Some(dir.to_path_buf()),
+            ..PacCacheConfig::default()
+        })
+    }
+
+    fn ttl(cache: &PacCache, headers: &CacheHeaders) -> Duration {
+        let directives = CacheDirectives::parse(headers.cache_control.as_deref());
+        cache.freshness(&directives, headers, now())
+    }
+
+    #[test]
+    fn freshness() {
+        let cache = PacCache::new(PacCacheConfig::default());
+        let secs = |s| Duration::from_secs(s);
+
+        assert_eq!(ttl(&cache, &headers(Some("max-age=600"), None)), secs(600));
+        assert_eq!(
+            ttl(&cache, &headers(Some("public, MAX-AGE=\"60\""), None)),
+            secs(60)
+        );
+        // max-age 优先于 Expires
+        let in_an_hour = http_date_at(now() + secs(3600));
+        assert_eq!(
+            ttl(&cache, &headers(Some("max-age=5"), Some(&in_an_hour))),
+            secs(5)
+        );
+        assert_eq!(ttl(&cache, &headers(None, Some(&in_an_hour))), secs(3600));
+        let an_hour_ago = http_date_at(now() - secs(3600));
+        assert_eq!(ttl(&cache, &headers(None, Some(&an_hour_ago))), secs(0));
+        assert_eq!(ttl(&cache, &headers(None, Some("0"))), secs(0));
+
+        assert_eq!(
+            ttl(&cache, &headers(Some("no-cache, max-age=600"), None)),
+            secs(0)
+        );
+        assert_eq!(ttl(&cache, &headers(Some("no-store"), None)), secs(0));
+
+        // 启发式：距 Last-Modified 时间的 10%
+        let mut modified = headers(None, None);
+        modified.validators.last_modified = Some(http_date_at(now() - secs(10 * 3600)));
+        assert_eq!(ttl(&cache, &modified), secs(3600));
+
+        assert_eq!(ttl(&cache, &headers(None, None)), cache.config.default_ttl);
+        assert_eq!(
+            ttl(&cache, &headers(Some("max-age=31536000"), None)),
+            cache.config.max_ttl
+        );
+    }
+
+    #[test]
+    fn not_modified_keeps_validators() {
+        let cache = PacCache::new(PacCacheConfig::default());
+        let mut original = headers(Some("max-age=0"), None);
+        original.validators = validators(Some("\"v1\""), Some("Sat, 17 Oct 2026 08:00:00 GMT"));
+        let script = cache
+            .update(
+                URL,
+                None,
+                Ok(FetchOutcome::Modified {
+                    script: "v1".to_string(),
+                    headers: original,
+                }),
+                now(),
+            )
+            .unwrap();
+        assert_eq!(script, "v1");
+        let cached = cache.get(URL).unwrap();
+        assert_eq!(cached.fresh_until, now());
+
+        // 304 未带校验器：沿用缓存中的 ETag 与 Last-Modified，并按新的 max-age 更新新鲜期
+        let later = now() + Duration::from_secs(30);
+        let revalidated = headers(Some("max-age=60"), None);
+        let script = cache
+            .update(
+                URL,
+                Some(cached),
+                Ok(FetchOutcome::NotModified {
+                    headers: revalidated,
+                }),
+                later,
+            )
+            .unwrap();
+        assert_eq!(script, "v1");
+        let cached = cache.get(URL).unwrap();
+        assert_eq!(
+            cached.validators,
+            validators(Some("\"v1\""), Some("Sat, 17 Oct 2026 08:00:00 GMT"))
+        );
+        assert_eq!(cached.fetched, later);
+        assert_eq!(cached.fresh_until, later + Duration::from_secs(60));
+
+        // 304 带新 ETag 时替换旧值
+        let mut renamed = headers(None, None);
+        renamed.validators.etag = Some("\"v1b\"".to_string());
+        cache
+            .update(
+                URL,
+                Some(cached),
+                Ok(FetchOutcome::NotModified { headers: renamed }),
+                later,
+            )
+            .unwrap();
+        assert_eq!(
+            cache.get(URL).unwrap().validators,
+            validators(Some("\"v1b\""), Some("Sat, 17 Oct 2026 08:00:00 GMT"))
+        );
+    }
+
+    #[test]
+    fn not_modified_without_a_cached_script() {
+        let cache = PacCache::new(PacCacheConfig::default());
+        let outcome = Ok(FetchOutcome::NotModified {
+            headers: headers(None, None),
+        });
+        assert!(cache.update(URL, None, outcome, now()).is_err());
+        assert!(cache.get(URL).is_none());
+    }
+
+    #[test]
+    fn last_known_good_after_a_failed_fetch() {
+        let cache = PacCache::new(PacCacheConfig::default());
+        cache.store(
+            URL,
+            "good".to_string(),
+            &headers(Some("max-age=0"), None),
+            now(),
+        );
+        let cached = cache.get(URL);
+
+        let failed = || Err::<FetchOutcome, _>("connection refused".into());
+        let later = now() + Duration::from_secs(600);
+        assert_eq!(cache.update(URL, cached, failed(), later).unwrap(), "good");
+        // 失败后在退避期内直接使用旧脚本，但不算作一次成功的下载
+        let backed_off = cache.get(URL).unwrap();
+        assert_eq!(backed_off.script, "good");
+        assert_eq!(backed_off.fetched, now());
+        assert_eq!(backed_off.fresh_until, later + cache.config.failure_backoff);
+
+        assert_eq!(
+            cache
+                .update(URL, None, failed(), later)
+                .unwrap_err()
+                .to_string(),
+            "connection refused"
+        );
+    }
+
+    #[test]
+    fn no_store_stays_off_disk() {
+        let dir = temp_dir("no-store");
+        let cache = disk_cache(&dir);
+        let path = disk_path(&dir, URL);
+
+        cache.store(
+            URL,
+            "v1".to_string(),
+            &headers(Some("max-age=60"), None),
+            now(),
+        );
+        assert!(path.exists());
+
+        // no-store 的新版本删除磁盘上的旧副本，只作为内存中的最后可用版本
+        cache.store(
+            URL,
+            "v2".to_string(),
+            &headers(Some("no-store"), None),
+            now(),
+        );
+        assert!(!path.exists());
+        let cached = cache.get(URL).unwrap();
+        assert_eq!(cached.script, "v2");
+        assert!(cached.no_store);
+        assert_eq!(cached.fresh_until, now());
+
+        // 新的缓存实例无法从磁盘得到该脚本
+        assert!(disk_cache(&dir).get(URL).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn disk_round_trip() {
+        let dir = temp_dir("round-trip");
+        let path = disk_path(&dir, URL);
+        let entry = CachedScript {
+            script: "function FindProxyForURL(url, host) {\n\n  return \"DIRECT\";\n}\n"
+                .to_string(),
+            validators: validators(Some("W/\"abc\""), Some("Sat, 17 Oct 2026 08:00:00 GMT")),
+            fetched: now(),
+            fresh_until: now() + Duration::from_secs(300),
+            no_store: false,
+        };
+        write_disk_entry(&dir, &path, URL, &entry).unwrap();
+        assert_eq!(read_disk_entry(&path, URL), Some(entry.clone()));
+        assert!(!path.with_extension("pac.tmp").exists());
+        // 文件属于另一个 URL（哈希冲突）时不使用
+        assert_eq!(read_disk_entry(&path, "http://other.corp/proxy.pac"), None);
+
+        let bare = CachedScript {
+            validators: Validators::default(),
+            ..entry.clone()
+        };
+        write_disk_entry(&dir, &path, URL, &bare).unwrap();
+        assert_eq!(read_disk_entry(&path, URL), Some(bare));
+
+        // 新的缓存实例从磁盘加载条目
+        write_disk_entry(&dir, &path, URL, &entry).unwrap();
+        assert_eq!(disk_cache(&dir).get(URL), Some(entry));
+
+        fs::write(&path, "not a cache file").unwrap();
+        assert_eq!(read_disk_entry(&path, URL), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn clear_removes_disk_entries() {
+        let dir = temp_dir("clear");
+        let cache = disk_cache(&dir);
+        cache.store(
+            URL,
+            "v1".to_string(),
+            &headers(Some("max-age=60"), None),
+            now(),
+        );
+        cache.clear();
+        assert!(!disk_path(&dir, URL).exists());
+        assert!(cache.get(URL).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
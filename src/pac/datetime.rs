@@ -0,0 +1,354 @@
+// src/pac/datetime.rs
+//! `weekdayRange`, `dateRange` and `timeRange` following the Netscape PAC spec.
+//!
+//! Every function takes the current time with its UTC offset; the local wall clock is
+//! used unless the last argument is `"GMT"`, in which case the UTC clock is used.
+//! Ranges are inclusive and wrap around (e.g. `weekdayRange("FRI", "MON")`,
+//! `dateRange("DEC", "JAN")`, `timeRange(18, 8)`), except when a year is involved.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, Timelike};
+
+/// A PAC helper argument: JS numbers and strings are all these functions accept.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PacArg {
+    Number(i64),
+    Text(String),
+}
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Strips a trailing `"GMT"` argument and picks the matching wall clock.
+fn split_gmt(args: &[PacArg], now: DateTime<FixedOffset>) -> (&[PacArg], NaiveDateTime) {
+    match args.split_last() {
+        Some((PacArg::Text(last), rest)) if last.eq_ignore_ascii_case("GMT") => {
+            (rest, now.naive_utc())
+        }
+        _ => (args, now.naive_local()),
+    }
+}
+
+fn in_range<T: PartialOrd>(current: T, start: T, end: T, wrap: bool) -> bool {
+    if start <= end {
+        start <= current && current <= end
+    } else {
+        wrap && (current >= start || current <= end)
+    }
+}
+
+/// weekdayRange(wd1, [wd2], ["GMT"])
+pub(crate) fn weekday_range(args: &[PacArg], now: DateTime<FixedOffset>) -> bool {
+    let (args, clock) = split_gmt(args, now);
+    let day_index = |arg: &PacArg| match arg {
+        PacArg::Text(name) => WEEKDAYS
+            .iter()
+            .position(|d| d.eq_ignore_ascii_case(name.trim())),
+        PacArg::Number(_) => None,
+    };
+    let current = clock.weekday().num_days_from_sunday() as usize;
+
+    match args {
+        [wd] => day_index(wd) == Some(current),
+        [wd1, wd2] => match (day_index(wd1), day_index(wd2)) {
+            (Some(start), Some(end)) => in_range(current, start, end, true),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateField {
+    Day(u32),
+    Month(u32),
+    Year(i32),
+}
+
+fn date_field(arg: &PacArg) -> Option<DateField> {
+    match arg {
+        PacArg::Number(n) if (1..=31).contains(n) => Some(DateField::Day(*n as u32)),
+        PacArg::Number(n) if *n > 31 => Some(DateField::Year(*n as i32)),
+        PacArg::Number(_) => None,
+        PacArg::Text(text) => {
+            let text = text.trim();
+            if let Ok(n) = text.parse::<i64>() {
+                return date_field(&PacArg::Number(n));
+            }
+            MONTHS
+                .iter()
+                .position(|m| m.eq_ignore_ascii_case(text))
+                .map(|m| DateField::Month(m as u32 + 1))
+        }
+    }
+}
+
+/// (year, month, day) with only the parts a `dateRange` bound specifies.
+type DateKey = (Option<i32>, Option<u32>, Option<u32>);
+
+/// Orders a half of the arguments as (year, month, day), keeping only the parts given.
+/// Returns `None` when a part is repeated or out of the Netscape argument order.
+fn date_key(fields: &[DateField]) -> Option<DateKey> {
+    let (mut year, mut month, mut day) = (None, None, None);
+    let mut last_rank = 0;
+    for field in fields {
+        // 参数顺序必须是 day, month, year
+        let rank = match field {
+            DateField::Day(d) => {
+                day = Some(*d);
+                1
+            }
+            DateField::Month(m) => {
+                month = Some(*m);
+                2
+            }
+            DateField::Year(y) => {
+                year = Some(*y);
+                3
+            }
+        };
+        if rank <= last_rank {
+            return None;
+        }
+        last_rank = rank;
+    }
+    Some((year, month, day))
+}
+
+/// dateRange(day) / (day1, day2) / (mon) / (month1, month2) / (year) / (year1, year2)
+/// / (day1, month1, day2, month2) / (month1, year1, month2, year2)
+/// / (day1, month1, year1, day2, month2, year2), each with an optional trailing "GMT".
+pub(crate) fn date_range(args: &[PacArg], now: DateTime<FixedOffset>) -> bool {
+    let (args, clock) = split_gmt(args, now);
+    let fields: Option<Vec<DateField>> = args.iter().map(date_field).collect();
+    let fields = match fields {
+        Some(fields) if !fields.is_empty() => fields,
+        _ => return false,
+    };
+
+    let (start, end) = if fields.len() == 1 {
+        (&fields[..], &fields[..])
+    } else if fields.len() % 2 == 0 && fields.len() <= 6 {
+        fields.split_at(fields.len() / 2)
+    } else {
+        return false;
+    };
+
+    let (start, end) = match (date_key(start), date_key(end)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return false,
+    };
+    // 两端必须给出相同的字段组合
+    let shape = |k: &DateKey| (k.0.is_some(), k.1.is_some(), k.2.is_some());
+    if shape(&start) != shape(&end) {
+        return false;
+    }
+
+    let current = (
+        start.0.map(|_| clock.year()),
+        start.1.map(|_| clock.month()),
+        start.2.map(|_| clock.day()),
+    );
+    // 含年份的范围不回绕，其余（如 DEC 到 JAN）跨年回绕
+    in_range(current, start, end, start.0.is_none())
+}
+
+/// timeRange(hour) / (hour1, hour2) / (hour1, min1, hour2, min2)
+/// / (hour1, min1, sec1, hour2, min2, sec2), each with an optional trailing "GMT".
+pub(crate) fn time_range(args: &[PacArg], now: DateTime<FixedOffset>) -> bool {
+    let (args, clock) = split_gmt(args, now);
+    let numbers: Option<Vec<i64>> = args
+        .iter()
+        .map(|arg| match arg {
+            PacArg::Number(n) => Some(*n),
+            PacArg::Text(text) => text.trim().parse().ok(),
+        })
+        .collect();
+    let numbers = match numbers {
+        Some(numbers) => numbers,
+        None => return false,
+    };
+
+    let seconds = |h: i64, m: i64, s: i64| h * 3600 + m * 60 + s;
+    let current = seconds(
+        clock.hour() as i64,
+        clock.minute() as i64,
+        clock.second() as i64,
+    );
+
+    match numbers[..] {
+        [hour] => clock.hour() as i64 == hour,
+        // 整点范围包含结束的那一个小时，例如 timeRange(9, 17) 到 17:59:59 为止
+        [h1, h2] => in_range(current, seconds(h1, 0, 0), seconds(h2, 59, 59), true),
+        [h1, m1, h2, m2] => in_range(current, seconds(h1, m1, 0), seconds(h2, m2, 59), true),
+        [h1, m1, s1, h2, m2, s2] => {
+            in_range(current, seconds(h1, m1, s1), seconds(h2, m2, s2), true)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(time).unwrap()
+    }
+
+    /// `args!(1, "JUN", 2026)` -> PAC arguments.
+    macro_rules! args {
+        ($($arg:expr),* $(,)?) => { [$(PacArg::from($arg)),*] };
+    }
+
+    impl From<i64> for PacArg {
+        fn from(n: i64) -> Self {
+            PacArg::Number(n)
+        }
+    }
+
+    impl From<&str> for PacArg {
+        fn from(text: &str) -> Self {
+            PacArg::Text(text.to_string())
+        }
+    }
+
+    /// Saturday 2026-10-17 14:30:15 at UTC+02:00.
+    const NOW: &str = "2026-10-17T14:30:15+02:00";
+
+    #[test]
+    fn invalid_date_ranges() {
+        let now = at(NOW);
+        // 两端字段组合不同
+        assert!(!date_range(&args!(1, "JUN"), now));
+        assert!(!date_range(&args!("OCT", 2026), now));
+        // 参数个数不对
+        assert!(!date_range(&args!(), now));
+        assert!(!date_range(&args!(1, "OCT", 31), now));
+        assert!(!date_range(
+            &args!(1, "JAN", 2026, 31, "DEC", 2026, 1, 1),
+            now
+        ));
+        assert!(!date_range(
+            &args!(1, "JAN", 2026, 31, "DEC", 2026, 2027),
+            now
+        ));
+        // 顺序必须是 day, month, year，且不能重复
+        assert!(!date_range(&args!("JAN", 1, "DEC", 31), now));
+        assert!(!date_range(&args!(1, 2, 30, 31), now));
+        // 无法识别的参数
+        assert!(!date_range(&args!("FOO"), now));
+        assert!(!date_range(&args!(0), now));
+        assert!(!date_range(&args!("OCT", "GMT", "GMT"), now));
+    }
+
+    #[test]
+    fn valid_date_range_shapes() {
+        let now = at(NOW);
+        assert!(date_range(
+            &args!(1, "OCT", 2026, 31, "OCT", 2026, "GMT"),
+            now
+        ));
+        assert!(date_range(&args!(1, "OCT", 31, "OCT"), now));
+        assert!(date_range(&args!("17"), now));
+        assert!(date_range(&args!(" oct "), now));
+        assert!(date_range(&args!(2025, 2027), now));
+        assert!(!date_range(&args!(2027), now));
+    }
+
+    #[test]
+    fn month_year_form() {
+        let range = args!("JAN", 2026, "MAR", 2026);
+        assert!(date_range(&range, at("2026-01-01T00:00:00+00:00")));
+        assert!(date_range(&range, at("2026-03-31T23:59:59+00:00")));
+        assert!(!date_range(&range, at("2026-04-01T00:00:00+00:00")));
+        assert!(!date_range(&range, at("2027-02-01T00:00:00+00:00")));
+    }
+
+    #[test]
+    fn year_spanning_ranges() {
+        // 不含年份：跨年回绕
+        let wrap = args!("DEC", "JAN");
+        assert!(date_range(&wrap, at("2026-12-20T12:00:00+00:00")));
+        assert!(date_range(&wrap, at("2027-01-10T12:00:00+00:00")));
+        assert!(!date_range(&wrap, at(NOW)));
+        let days = args!(25, 5);
+        assert!(date_range(&days, at("2026-10-28T12:00:00+00:00")));
+        assert!(date_range(&days, at("2026-11-03T12:00:00+00:00")));
+        assert!(!date_range(&days, at(NOW)));
+
+        // 含年份：按 (年, 月, 日) 比较，不回绕
+        let spanning = args!("DEC", 2026, "JAN", 2027);
+        assert!(date_range(&spanning, at("2026-12-01T00:00:00+00:00")));
+        assert!(date_range(&spanning, at("2027-01-31T00:00:00+00:00")));
+        assert!(!date_range(&spanning, at("2027-12-01T00:00:00+00:00")));
+        assert!(!date_range(&spanning, at("2026-01-15T00:00:00+00:00")));
+        let full = args!(20, "DEC", 2026, 10, "JAN", 2027);
+        assert!(date_range(&full, at("2027-01-10T08:00:00+00:00")));
+        assert!(!date_range(&full, at("2027-01-11T08:00:00+00:00")));
+        assert!(!date_range(&full, at("2026-12-19T08:00:00+00:00")));
+        assert!(!date_range(&args!(2027, 2026), at(NOW)));
+        assert!(!date_range(
+            &args!("MAR", 2027, "JAN", 2027),
+            at("2027-02-01T00:00:00+00:00")
+        ));
+    }
+
+    #[test]
+    fn trailing_gmt() {
+        // 本地时间已是 17 日，UTC 仍是 16 日
+        let now = at("2026-10-17T01:00:00+02:00");
+        assert!(date_range(&args!(17), now));
+        assert!(!date_range(&args!(17, "GMT"), now));
+        assert!(date_range(&args!(16, "gmt"), now));
+        assert!(weekday_range(&args!("SAT"), now));
+        assert!(weekday_range(&args!("FRI", "GMT"), now));
+        assert!(time_range(&args!(1), now));
+        assert!(time_range(&args!(23, "GMT"), now));
+        // "GMT" 只能出现在最后
+        assert!(!date_range(&args!("GMT", 16), now));
+    }
+
+    #[test]
+    fn time_ranges() {
+        // 只给小时时包含结束的那一整个小时
+        let hours = args!(9, 17);
+        assert!(time_range(&hours, at("2026-10-17T17:59:59+02:00")));
+        assert!(!time_range(&hours, at("2026-10-17T18:00:00+02:00")));
+        assert!(!time_range(&hours, at("2026-10-17T08:59:59+02:00")));
+        let night = args!(22, 6);
+        assert!(time_range(&night, at("2026-10-17T23:00:00+02:00")));
+        assert!(time_range(&night, at("2026-10-17T06:30:00+02:00")));
+        assert!(!time_range(&night, at("2026-10-17T07:00:00+02:00")));
+        // 分钟形式包含结束的那一整分钟
+        assert!(time_range(&args!(14, 0, 14, 30), at(NOW)));
+        assert!(!time_range(&args!(14, 0, 14, 29), at(NOW)));
+        assert!(time_range(
+            &args!("14", "30", "15", "14", "30", "15"),
+            at(NOW)
+        ));
+        assert!(time_range(&args!(14, 15), at(NOW)));
+        let invalid: [&[PacArg]; 4] = [
+            &args!(),
+            &args!(14, 30, 15),
+            &args!(1, 2, 3, 4, 5),
+            &args!("noon"),
+        ];
+        for invalid in invalid {
+            assert!(!time_range(invalid, at(NOW)), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn weekday_ranges() {
+        let now = at(NOW);
+        assert!(weekday_range(&args!("sat"), now));
+        assert!(weekday_range(&args!("THU", "SUN"), now));
+        assert!(weekday_range(&args!("FRI", "MON"), now));
+        assert!(!weekday_range(&args!("MON", "FRI"), now));
+        assert!(!weekday_range(&args!(6), now));
+        assert!(!weekday_range(&args!("SAT", "SUN", "MON"), now));
+        assert!(!weekday_range(&args!(), now));
+    }
+}
@@ -0,0 +1,291 @@
+// src/pac/decode.rs
+//! Turns the raw bytes of a PAC file into script text, rejecting things that are clearly
+//! not a PAC script (captive-portal and SSO login pages served with HTTP 200).
+
+use super::error::PacError;
+use crate::log_warn;
+
+/// Content types under which PAC files are served in practice. Browsers do not enforce
+/// the content type, so anything else that is not HTML only produces a warning.
+const PAC_CONTENT_TYPES: [&str; 8] = [
+    "application/x-ns-proxy-autoconfig",
+    "application/x-javascript-config",
+    "application/javascript",
+    "application/x-javascript",
+    "application/ecmascript",
+    "text/javascript",
+    "text/plain",
+    "application/octet-stream",
+];
+
+/// Decodes a PAC file fetched from `location`, with the `Content-Type` header (or the
+/// media type of a `data:` URL) when there is one.
+///
+/// The encoding is taken from the byte order mark, then the `charset` parameter, then
+/// sniffed: UTF-16 without BOM is recognised by its NUL bytes, and bytes that are not
+/// valid UTF-8 are read as Windows-1252, like browsers do.
+pub(crate) fn decode_pac(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    location: &str,
+) -> Result<String, PacError> {
+    let not_pac = |reason: String| PacError::NotAPacScript {
+        location: location.to_string(),
+        reason,
+    };
+
+    let (mime, charset) = split_content_type(content_type.unwrap_or_default());
+    if matches!(mime.as_str(), "text/html" | "application/xhtml+xml") {
+        return Err(not_pac(format!("server returned {}", mime)));
+    }
+    if !mime.is_empty() && !PAC_CONTENT_TYPES.contains(&mime.as_str()) {
+        log_warn!("PAC file {} has unexpected content type {}", location, mime);
+    }
+
+    let script = decode_text(bytes, charset.as_deref(), location);
+    if looks_like_html(&script) {
+        return Err(not_pac("content looks like an HTML page".to_string()));
+    }
+    Ok(script)
+}
+
+/// `"Text/HTML; Charset=\"UTF-8\""` -> (`"text/html"`, `Some("utf-8")`).
+fn split_content_type(content_type: &str) -> (String, Option<String>) {
+    let mut parts = content_type.split(';');
+    let mime = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let charset = parts.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_ascii_lowercase())
+    });
+    (mime, charset)
+}
+
+fn decode_text(bytes: &[u8], charset: Option<&str>, location: &str) -> String {
+    // BOM 优先于 charset 参数（与 WHATWG 编码规范一致）
+    if let Some(rest) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    match charset {
+        Some("utf-8" | "utf8") => String::from_utf8_lossy(bytes).into_owned(),
+        Some("utf-16le" | "utf-16") => decode_utf16(bytes, u16::from_le_bytes),
+        Some("utf-16be") => decode_utf16(bytes, u16::from_be_bytes),
+        Some("iso-8859-1" | "latin1" | "us-ascii" | "ascii" | "windows-1252" | "cp1252") => {
+            decode_windows_1252(bytes)
+        }
+        Some(other) => {
+            log_warn!(
+                "PAC file {} declares unsupported charset {}; decoding as UTF-8",
+                location,
+                other
+            );
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        None => sniff(bytes),
+    }
+}
+
+fn sniff(bytes: &[u8]) -> String {
+    // 无 BOM 的 UTF-16：ASCII 字符的高字节为 0
+    let sample = &bytes[..bytes.len().min(512) & !1];
+    let zeros_at = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    if sample.len() >= 4 {
+        let half = sample.len() / 2;
+        if zeros_at(1) * 2 > half && zeros_at(0) == 0 {
+            return decode_utf16(bytes, u16::from_le_bytes);
+        }
+        if zeros_at(0) * 2 > half && zeros_at(1) == 0 {
+            return decode_utf16(bytes, u16::from_be_bytes);
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => decode_windows_1252(bytes),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Windows-1252 characters for bytes 0x80..=0x9F; the rest map to U+0000..=U+00FF.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => WINDOWS_1252_HIGH[usize::from(b - 0x80)],
+            _ => char::from(b),
+        })
+        .collect()
+}
+
+/// JavaScript cannot start with a tag, so markup at the start means an HTML (or XML)
+/// page. `<!--` is allowed: legacy scripts may open with an HTML comment.
+fn looks_like_html(script: &str) -> bool {
+    let start = script.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    let mut chars = start.chars();
+    chars.next() == Some('<')
+        && !start.starts_with("<!--")
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '!' || c == '?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "function FindProxyForURL(url, host) { return \"DIRECT\"; }";
+    const LOCATION: &str = "http://wpad.corp/wpad.dat";
+
+    fn decode(bytes: &[u8], content_type: Option<&str>) -> String {
+        decode_pac(bytes, content_type, LOCATION).unwrap()
+    }
+
+    fn utf16(text: &str, unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        text.encode_utf16().flat_map(unit).collect()
+    }
+
+    #[test]
+    fn byte_order_marks() {
+        let utf8 = [&[0xef, 0xbb, 0xbf][..], "// é\n".as_bytes()].concat();
+        assert_eq!(decode(&utf8, None), "// é\n");
+        let le = [&[0xff, 0xfe][..], &utf16("// é", u16::to_le_bytes)].concat();
+        assert_eq!(decode(&le, None), "// é");
+        let be = [&[0xfe, 0xff][..], &utf16("// é", u16::to_be_bytes)].concat();
+        assert_eq!(decode(&be, None), "// é");
+        // BOM 优先于 charset 参数
+        assert_eq!(decode(&le, Some("text/plain; charset=utf-8")), "// é");
+        assert_eq!(
+            decode(&utf8, Some("text/plain; charset=iso-8859-1")),
+            "// é\n"
+        );
+    }
+
+    #[test]
+    fn content_type_charset() {
+        assert_eq!(
+            split_content_type("Text/PLAIN; foo=bar; Charset=\"UTF-8\""),
+            ("text/plain".to_string(), Some("utf-8".to_string()))
+        );
+        assert_eq!(split_content_type(""), (String::new(), None));
+
+        let latin1 = b"// caf\xe9";
+        assert_eq!(
+            decode(latin1, Some("text/plain; charset=ISO-8859-1")),
+            "// café"
+        );
+        // 即使是合法的 UTF-8，也按声明的 charset 解码
+        assert_eq!(
+            decode("// é".as_bytes(), Some("text/plain; charset=windows-1252")),
+            "// Ã©"
+        );
+        assert_eq!(
+            decode(
+                &utf16("// x", u16::to_le_bytes),
+                Some("text/plain; charset=utf-16le")
+            ),
+            "// x"
+        );
+        assert_eq!(
+            decode(
+                &utf16("// x", u16::to_be_bytes),
+                Some("text/plain; charset=UTF-16BE")
+            ),
+            "// x"
+        );
+        // 不支持的 charset 按 UTF-8 解码
+        assert_eq!(
+            decode("// é".as_bytes(), Some("text/plain; charset=koi8-r")),
+            "// é"
+        );
+    }
+
+    #[test]
+    fn utf16_without_bom() {
+        assert_eq!(decode(&utf16(SCRIPT, u16::to_le_bytes), None), SCRIPT);
+        assert_eq!(decode(&utf16(SCRIPT, u16::to_be_bytes), None), SCRIPT);
+        // 太短的样本不做 UTF-16 检测
+        assert_eq!(decode(b"a\0", None), "a\0");
+    }
+
+    #[test]
+    fn windows_1252_fallback() {
+        assert_eq!(decode(b"// \x80 \x93x\x94 caf\xe9", None), "// € “x” café");
+        assert_eq!(decode(b"// \x81", None), "// \u{81}");
+        assert_eq!(decode("// café".as_bytes(), None), "// café");
+    }
+
+    #[test]
+    fn html_is_rejected() {
+        for page in [
+            "<!DOCTYPE html><html><body>Sign in</body></html>",
+            "  \n<html>",
+            "<?xml version=\"1.0\"?><html/>",
+            "\u{feff}<HEAD>",
+        ] {
+            let err = decode_pac(page.as_bytes(), None, LOCATION).unwrap_err();
+            assert_eq!(
+                err,
+                PacError::NotAPacScript {
+                    location: LOCATION.to_string(),
+                    reason: "content looks like an HTML page".to_string(),
+                },
+                "{:?}",
+                page
+            );
+        }
+
+        let err = decode_pac(
+            SCRIPT.as_bytes(),
+            Some("text/html; charset=utf-8"),
+            LOCATION,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "http://wpad.corp/wpad.dat is not a PAC script: server returned text/html"
+        );
+        assert!(decode_pac(SCRIPT.as_bytes(), Some("application/xhtml+xml"), LOCATION).is_err());
+    }
+
+    #[test]
+    fn scripts_that_start_like_markup() {
+        // 旧脚本可能以 HTML 注释开头
+        let commented = format!("<!--\n{}\n// -->", SCRIPT);
+        assert_eq!(decode(commented.as_bytes(), None), commented);
+        for script in ["< 1", "<= 2", "<", ""] {
+            assert_eq!(decode(script.as_bytes(), None), script);
+        }
+        // 非常见但非 HTML 的类型只记录警告
+        assert_eq!(decode(SCRIPT.as_bytes(), Some("application/json")), SCRIPT);
+        assert_eq!(
+            decode(SCRIPT.as_bytes(), Some("application/x-ns-proxy-autoconfig")),
+            SCRIPT
+        );
+    }
+}
@@ -1,21 +1,616 @@
 // src/pac/downloader.rs
-use crate::{log_error, log_info};
-use reqwest::blocking::Client;
+use super::decode::decode_pac;
+use crate::env_proxy::get_env_proxy;
+use crate::proxy::ProxyDescriptor;
+use crate::{log_error, log_info, log_warn};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{
+    HeaderMap, CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED,
+};
+use reqwest::{Certificate, StatusCode};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::thread;
 use std::time::Duration;
 
+/// How PAC scripts are downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloaderConfig {
+    /// Longest time to establish the connection (including the TLS handshake).
+    pub connect_timeout: Duration,
+    /// Longest time one download attempt may take as a whole, from connecting to the
+    /// last byte of the body. Every retry gets the full time again.
+    pub request_timeout: Duration,
+    /// Extra attempts after a failed download. Only connection errors, timeouts and
+    /// `408`, `429` and `5xx` responses are retried.
+    pub retries: u32,
+    /// Wait before the first retry; doubled for every further one.
+    pub retry_backoff: Duration,
+    /// Responses larger than this are rejected.
+    pub max_body_bytes: usize,
+    /// PEM bundles whose certificates are trusted in addition to the built-in roots,
+    /// e.g. a corporate CA serving the PAC file over HTTPS.
+    pub extra_root_certificates: Vec<PathBuf>,
+    pub user_agent: String,
+    /// How the download itself is routed. Defaults to [`PacFetchRoute::Direct`].
+    pub route: PacFetchRoute,
+}
+
+/// How the PAC download is routed.
+///
+/// The PAC script decides which proxy to use, so fetching it through a proxy chosen by
+/// the same machinery can loop or fail. reqwest would otherwise apply `HTTP_PROXY` and
+/// the system proxy on its own; every route here overrides that.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PacFetchRoute {
+    /// Connect to the PAC server directly.
+    #[default]
+    Direct,
+    /// Always go through this HTTP or HTTPS proxy.
+    Proxy(ProxyDescriptor),
+    /// Use the proxy from the environment variables (`HTTP_PROXY`, `HTTPS_PROXY`,
+    /// honouring `NO_PROXY`) for the PAC URL, or connect directly when none applies.
+    Environment,
+}
+
+impl Default for DownloaderConfig {
+    fn default() -> Self {
+        DownloaderConfig {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(15),
+            retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            max_body_bytes: 8 * 1024 * 1024,
+            extra_root_certificates: Vec::new(),
+            user_agent: concat!("proxyparser/", env!("CARGO_PKG_VERSION")).to_string(),
+            route: PacFetchRoute::Direct,
+        }
+    }
+}
+
+/// Downloads the PAC script at `pac_url` with the default [`DownloaderConfig`],
+/// bypassing any cache.
+///
+/// Use [`PacCache`](super::PacCache) to honour HTTP freshness headers and to fall back
+/// to the last known good script when the server is unreachable.
 pub fn download_pac(pac_url: &str) -> Result<String, Box<dyn std::error::Error>> {
-    log_info!("Downloading PAC script from: {}", pac_url);
+    download_pac_with(pac_url, &DownloaderConfig::default())
+}
+
+/// Like [`download_pac`], with an explicit configuration.
+pub fn download_pac_with(
+    pac_url: &str,
+    config: &DownloaderConfig,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match fetch_pac(pac_url, &Validators::default(), config)? {
+        FetchOutcome::Modified { script, .. } => Ok(script),
+        // 没有发送条件请求，服务器不应返回 304
+        FetchOutcome::NotModified { .. } => Err("unexpected HTTP 304 Not Modified".into()),
+    }
+}
+
+/// Cache validators sent with a conditional request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// The caching-relevant response headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CacheHeaders {
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub validators: Validators,
+}
+
+pub(crate) enum FetchOutcome {
+    Modified {
+        script: String,
+        headers: CacheHeaders,
+    },
+    NotModified {
+        headers: CacheHeaders,
+    },
+}
+
+/// One failed attempt, and whether trying again may help.
+struct AttemptError {
+    error: Box<dyn std::error::Error>,
+    retryable: bool,
+}
+
+/// Fetches `pac_url`, conditionally when `validators` are given, retrying transient
+/// failures as configured.
+pub(crate) fn fetch_pac(
+    pac_url: &str,
+    validators: &Validators,
+    config: &DownloaderConfig,
+) -> Result<FetchOutcome, Box<dyn std::error::Error>> {
+    let client = build_client(pac_url, config)?;
+
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 0;
+    loop {
+        match fetch_once(&client, pac_url, validators, config) {
+            Ok(outcome) => return Ok(outcome),
+            Err(e) if e.retryable && attempt < config.retries => {
+                attempt += 1;
+                log_warn!(
+                    "PAC download from {} failed: {}; retry {}/{} in {:?}",
+                    pac_url,
+                    e.error,
+                    attempt,
+                    config.retries,
+                    backoff
+                );
+                thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+            Err(e) => {
+                log_error!("PAC download from {} failed: {}", pac_url, e.error);
+                return Err(e.error);
+            }
+        }
+    }
+}
+
+fn build_client(
+    pac_url: &str,
+    config: &DownloaderConfig,
+) -> Result<Client, Box<dyn std::error::Error>> {
+    let mut builder = Client::builder()
+        .connect_timeout(config.connect_timeout)
+        // blocking 客户端的 timeout 限制整个请求（连接、响应头和读取 body）
+        .timeout(config.request_timeout)
+        .user_agent(config.user_agent.as_str());
+
+    let proxy = match &config.route {
+        PacFetchRoute::Direct => None,
+        PacFetchRoute::Proxy(proxy) => Some(proxy.clone()),
+        PacFetchRoute::Environment => get_env_proxy(pac_url),
+    };
+    match proxy.filter(|proxy| !proxy.is_direct()) {
+        None => {
+            log_info!("Downloading PAC script from {} (direct)", pac_url);
+            builder = builder.no_proxy();
+        }
+        Some(proxy) => {
+            let (scheme, server) = match (proxy.scheme(), proxy.server()) {
+                (Some(scheme @ ("http" | "https")), Some(server)) => (scheme, server),
+                _ => {
+                    return Err(format!(
+                        "PAC downloads can only go through HTTP or HTTPS proxies, not {}",
+                        proxy.scheme().unwrap_or_default()
+                    )
+                    .into())
+                }
+            };
+            // 日志中不输出代理凭据
+            log_info!(
+                "Downloading PAC script from {} via {} proxy {}:{}",
+                pac_url,
+                scheme,
+                server.host,
+                server.port
+            );
+            builder = builder.proxy(reqwest::Proxy::all(proxy.to_string())?);
+        }
+    }
+    for path in &config.extra_root_certificates {
+        let pem = fs::read(path)
+            .map_err(|e| format!("cannot read CA bundle {}: {}", path.display(), e))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("invalid CA bundle {}: {}", path.display(), e))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+fn fetch_once(
+    client: &Client,
+    pac_url: &str,
+    validators: &Validators,
+    config: &DownloaderConfig,
+) -> Result<FetchOutcome, AttemptError> {
+    let mut request = client.get(pac_url);
+    if let Some(etag) = &validators.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = request.send().map_err(|e| AttemptError {
+        retryable: e.is_connect() || e.is_timeout() || e.is_request(),
+        error: Box::new(e),
+    })?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        log_info!("PAC script not modified: {}", pac_url);
+        return Ok(FetchOutcome::NotModified {
+            headers: cache_headers(response.headers()),
+        });
+    }
+    if !status.is_success() {
+        return Err(AttemptError {
+            error: format!("HTTP {}", status).into(),
+            retryable: status.is_server_error()
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS,
+        });
+    }
+
+    let headers = cache_headers(response.headers());
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = read_body(response, config.max_body_bytes)?;
+    let script = decode_pac(&body, content_type.as_deref(), pac_url).map_err(|e| AttemptError {
+        error: Box::new(e),
+        retryable: false,
+    })?;
+    log_info!(
+        "PAC script downloaded successfully ({} bytes)",
+        script.len()
+    );
+    Ok(FetchOutcome::Modified { script, headers })
+}
+
+/// Reads at most `max_bytes`, so an oversized (or endless) response cannot exhaust memory.
+fn read_body(response: Response, max_bytes: usize) -> Result<Vec<u8>, AttemptError> {
+    let too_large = |size: String| AttemptError {
+        error: format!("PAC response is {} bytes, limit is {}", size, max_bytes).into(),
+        retryable: false,
+    };
+    if let Some(length) = response.content_length() {
+        if length > max_bytes as u64 {
+            return Err(too_large(length.to_string()));
+        }
+    }
+
+    let mut body = Vec::new();
+    response
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| AttemptError {
+            error: Box::new(e),
+            retryable: true,
+        })?;
+    if body.len() > max_bytes {
+        return Err(too_large(format!("more than {}", max_bytes)));
+    }
+    Ok(body)
+}
+
+fn cache_headers(headers: &HeaderMap) -> CacheHeaders {
+    let get = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    CacheHeaders {
+        cache_control: get(CACHE_CONTROL),
+        expires: get(EXPIRES),
+        validators: Validators {
+            etag: get(ETAG),
+            last_modified: get(LAST_MODIFIED),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+    use std::io::{ErrorKind, Write};
+    use std::net::TcpListener;
+    use std::sync::{Mutex, MutexGuard};
+
+    const SCRIPT: &str = "function FindProxyForURL(url, host) { return 'DIRECT'; }";
+
+    fn response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/x-ns-proxy-autoconfig\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    /// Reads one request head from every accepted connection and answers it with the
+    /// next of `responses`. Returns the listening address and the received heads.
+    fn serve(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                requests.push(String::from_utf8_lossy(&head).into_owned());
+                let _ = stream.write_all(response.as_bytes());
+            }
+            requests
+        });
+        (address, server)
+    }
+
+    fn fast_retries() -> DownloaderConfig {
+        DownloaderConfig {
+            retry_backoff: Duration::from_millis(1),
+            ..DownloaderConfig::default()
+        }
+    }
+
+    fn download(
+        address: &str,
+        config: &DownloaderConfig,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        download_pac_with(&format!("http://{}/proxy.pac", address), config)
+    }
 
-    let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
+    #[test]
+    fn body_size_limit() {
+        let config = DownloaderConfig {
+            max_body_bytes: SCRIPT.len(),
+            ..fast_retries()
+        };
+        // 正好等于上限的响应可以接受
+        let (address, server) = serve(vec![response("200 OK", SCRIPT)]);
+        assert_eq!(download(&address, &config).unwrap(), SCRIPT);
+        server.join().unwrap();
 
-    let response = client.get(pac_url).send()?;
+        // Content-Length 超限时不读 body，也不重试
+        let oversized = format!("{} ", SCRIPT);
+        let (address, server) = serve(vec![response("200 OK", &oversized)]);
+        let err = download(&address, &config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "PAC response is {} bytes, limit is {}",
+                oversized.len(),
+                SCRIPT.len()
+            )
+        );
+        assert_eq!(server.join().unwrap().len(), 1);
 
-    if !response.status().is_success() {
-        log_error!("PAC download failed: HTTP {}", response.status());
-        return Err(format!("HTTP {}", response.status()).into());
+        // 没有 Content-Length：读到上限加一个字节为止
+        let (address, server) = serve(vec![format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{}",
+            oversized
+        )]);
+        let err = download(&address, &config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "PAC response is more than {} bytes, limit is {}",
+                SCRIPT.len(),
+                SCRIPT.len()
+            )
+        );
+        server.join().unwrap();
     }
 
-    let text = response.text()?;
-    log_info!("PAC script downloaded successfully ({} bytes)", text.len());
-    Ok(text)
+    #[test]
+    fn retries_transient_failures() {
+        let (address, server) = serve(vec![
+            response("503 Service Unavailable", ""),
+            response("200 OK", SCRIPT),
+        ]);
+        let config = DownloaderConfig {
+            retries: 1,
+            ..fast_retries()
+        };
+        assert_eq!(download(&address, &config).unwrap(), SCRIPT);
+        assert_eq!(server.join().unwrap().len(), 2);
+
+        // 重试次数用完后返回最后一次的错误
+        let (address, server) = serve(vec![
+            response("503 Service Unavailable", ""),
+            response("429 Too Many Requests", ""),
+        ]);
+        let err = download(&address, &config).unwrap_err();
+        assert_eq!(err.to_string(), "HTTP 429 Too Many Requests");
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (address, server) = serve(vec![response("404 Not Found", "")]);
+        let config = DownloaderConfig {
+            retries: 3,
+            ..fast_retries()
+        };
+        let err = download(&address, &config).unwrap_err();
+        assert_eq!(err.to_string(), "HTTP 404 Not Found");
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ca_bundle_errors() {
+        let dir = std::env::temp_dir();
+        let missing = dir.join(format!("proxyparser-missing-{}.pem", std::process::id()));
+        let config = DownloaderConfig {
+            extra_root_certificates: vec![missing.clone()],
+            ..fast_retries()
+        };
+        let err = build_client("http://wpad.corp/proxy.pac", &config).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("cannot read CA bundle {}", missing.display())),
+            "{}",
+            err
+        );
+
+        let invalid = dir.join(format!("proxyparser-invalid-{}.pem", std::process::id()));
+        fs::write(
+            &invalid,
+            "-----BEGIN CERTIFICATE-----\nnot a certificate\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let config = DownloaderConfig {
+            extra_root_certificates: vec![invalid.clone()],
+            ..fast_retries()
+        };
+        let err = build_client("http://wpad.corp/proxy.pac", &config).unwrap_err();
+        let _ = fs::remove_file(&invalid);
+        assert!(
+            err.to_string()
+                .starts_with(&format!("invalid CA bundle {}", invalid.display())),
+            "{}",
+            err
+        );
+    }
+
+    const PROXY_VARS: [&str; 6] = [
+        "http_proxy",
+        "HTTP_PROXY",
+        "https_proxy",
+        "HTTPS_PROXY",
+        "no_proxy",
+        "NO_PROXY",
+    ];
+
+    // 环境变量是进程级的，修改它们的测试串行执行
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Replaces the proxy variables for the lifetime of the guard.
+    struct ProxyEnv {
+        saved: Vec<(&'static str, Option<OsString>)>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl ProxyEnv {
+        fn set(vars: &[(&str, &str)]) -> Self {
+            let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let saved = PROXY_VARS
+                .iter()
+                .map(|&name| (name, std::env::var_os(name)))
+                .collect();
+            for name in PROXY_VARS {
+                std::env::remove_var(name);
+            }
+            for (name, value) in vars {
+                std::env::set_var(name, value);
+            }
+            ProxyEnv { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for ProxyEnv {
+        fn drop(&mut self) {
+            for (name, value) in &self.saved {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+
+    /// A proxy that must never be contacted.
+    fn unused_proxy() -> TcpListener {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        listener
+    }
+
+    fn assert_not_contacted(proxy: &TcpListener) {
+        let err = proxy.accept().map(|_| ()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    }
+
+    fn routed(route: PacFetchRoute) -> DownloaderConfig {
+        DownloaderConfig {
+            route,
+            ..fast_retries()
+        }
+    }
+
+    #[test]
+    fn direct_route_ignores_proxy_variables() {
+        let proxy = unused_proxy();
+        let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+        let _env = ProxyEnv::set(&[("http_proxy", &proxy_url), ("HTTP_PROXY", &proxy_url)]);
+
+        let (address, server) = serve(vec![response("200 OK", SCRIPT)]);
+        assert_eq!(
+            download(&address, &routed(PacFetchRoute::Direct)).unwrap(),
+            SCRIPT
+        );
+        assert!(server.join().unwrap()[0].starts_with("GET /proxy.pac "));
+        assert_not_contacted(&proxy);
+    }
+
+    #[test]
+    fn explicit_proxy_route() {
+        let (proxy, server) = serve(vec![response("200 OK", SCRIPT)]);
+        let route = PacFetchRoute::Proxy(format!("http://{}", proxy).parse().unwrap());
+        assert_eq!(
+            download_pac_with("http://wpad.invalid/proxy.pac", &routed(route)).unwrap(),
+            SCRIPT
+        );
+        // 经 HTTP 代理时请求行是绝对 URL
+        assert!(server.join().unwrap()[0].starts_with("GET http://wpad.invalid/proxy.pac "));
+
+        for proxy in ["socks5://127.0.0.1:1080", "socks4://127.0.0.1:1080"] {
+            let route = PacFetchRoute::Proxy(proxy.parse().unwrap());
+            let err =
+                download_pac_with("http://wpad.invalid/proxy.pac", &routed(route)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "PAC downloads can only go through HTTP or HTTPS proxies, not {}",
+                    &proxy[..6]
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn environment_route_uses_proxy_variables() {
+        let (proxy, server) = serve(vec![response("200 OK", SCRIPT)]);
+        let proxy_url = format!("http://{}", proxy);
+        let _env = ProxyEnv::set(&[("HTTP_PROXY", &proxy_url)]);
+        assert_eq!(
+            download_pac_with(
+                "http://wpad.invalid/proxy.pac",
+                &routed(PacFetchRoute::Environment)
+            )
+            .unwrap(),
+            SCRIPT
+        );
+        assert!(server.join().unwrap()[0].starts_with("GET http://wpad.invalid/proxy.pac "));
+    }
+
+    #[test]
+    fn environment_route_honours_no_proxy() {
+        let proxy = unused_proxy();
+        let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+        let _env = ProxyEnv::set(&[("HTTP_PROXY", &proxy_url), ("NO_PROXY", "127.0.0.1")]);
+
+        let (address, server) = serve(vec![response("200 OK", SCRIPT)]);
+        assert_eq!(
+            download(&address, &routed(PacFetchRoute::Environment)).unwrap(),
+            SCRIPT
+        );
+        assert!(server.join().unwrap()[0].starts_with("GET /proxy.pac "));
+        assert_not_contacted(&proxy);
+    }
 }
@@ -1,75 +1,664 @@
 // src/pac/engine.rs
-use super::downloader::download_pac;
+use super::cache::PacCache;
+use super::datetime::{date_range, time_range, weekday_range, PacArg};
+use super::error::{PacError, PacLimit};
+use super::host::{PacHost, SystemHost};
+use super::net::{ip_in_net, ip_in_prefix, join_ip_list, parse_ip, sort_ip_list};
+use super::result::{parse_pac_result, PacResult};
+use super::shexp::compile_shexp;
+use super::source::PacSource;
+use super::url_policy::UrlPolicy;
+use crate::hostname::canonicalize_host;
 use crate::log_warn;
+use crate::proxy::ProxyDescriptor;
 use boa_engine::{
-    js_string, Context, JsNativeError, JsResult, JsString, JsValue, NativeFunction, Source,
+    js_string, Context, JsError, JsNativeError, JsResult, JsString, JsValue, NativeFunction,
+    Script, Source,
 };
-use chrono::{Datelike, Utc, Weekday};
 use regex::Regex;
-use std::net::{Ipv4Addr, ToSocketAddrs, UdpSocket};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::task::{self, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 use url::Url;
 
-pub fn evaluate_pac_for_url(
-    pac_url: &str,
-    target_url: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let script = download_pac(pac_url)?;
-    let url_obj = Url::parse(target_url)?;
-    let host = url_obj.host_str().ok_or("URL has no host")?.to_string();
+/// Settings for a [`PacEngine`].
+///
+/// `PacOptions` is `Send + Sync` and cheap to clone, so one value can be shared by
+/// engines built on different threads.
+#[derive(Clone)]
+pub struct PacOptions {
+    /// Name resolution, local addresses and clock seen by the script.
+    pub host: Arc<dyn PacHost>,
+    /// Bounds on how much work the (untrusted) script may do.
+    pub limits: PacLimits,
+    /// How much of the target URL is passed to `FindProxyForURL`.
+    pub url_policy: UrlPolicy,
+}
 
-    let raw_result = execute_pac_script(&script, target_url, &host)?;
-    let normalized = normalize_pac_result(&raw_result);
+impl Default for PacOptions {
+    fn default() -> Self {
+        PacOptions {
+            host: Arc::new(SystemHost::default()),
+            limits: PacLimits::default(),
+            url_policy: UrlPolicy::default(),
+        }
+    }
+}
 
-    Ok(normalized)
+/// Execution limits for PAC scripts, which are untrusted code downloaded from the network.
+///
+/// Exceeding a limit stops the script and returns [`PacError::LimitExceeded`].
+///
+/// Loop iterations, recursion depth and stack size are enforced by boa itself. The script
+/// runs on the engine's worker thread, which checks `timeout` every few thousand VM
+/// instructions and in every PAC helper call, and stops the script once it has passed.
+/// The caller never waits longer than `timeout` either. JavaScript called back from
+/// boa's own native code (`forEach`, `sort`, `replace` callbacks, …) cannot be
+/// interrupted, so a worker stuck there is abandoned and keeps running until the
+/// callbacks finish or a boa limit stops them. While [`MAX_STRAY_WORKERS`] such workers
+/// are alive, new engines are refused. An engine whose call timed out refuses further
+/// lookups (see [`PacEngine`]).
+///
+/// Heap growth is **not** capped: boa 0.19 has no allocation accounting, so memory is
+/// only bounded indirectly by `max_script_bytes` and the loop/recursion limits. A
+/// hostile script can still allocate until the process runs out of memory. Evaluate
+/// scripts in a separate process if that matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacLimits {
+    /// Maximum iterations of the loops of a single function call.
+    pub loop_iteration_limit: u64,
+    /// Maximum depth of nested JavaScript calls.
+    pub recursion_limit: usize,
+    /// Maximum length of the VM value stack.
+    pub stack_size_limit: usize,
+    /// Wall-clock budget for evaluating the script and for each `FindProxyForURL` call,
+    /// including DNS lookups made by the script.
+    pub timeout: Duration,
+    /// Largest PAC script accepted, in bytes.
+    pub max_script_bytes: usize,
 }
 
-fn execute_pac_script(script: &str, url: &str, host: &str) -> JsResult<String> {
-    let mut context = Context::default();
+impl Default for PacLimits {
+    fn default() -> Self {
+        PacLimits {
+            loop_iteration_limit: 1_000_000,
+            recursion_limit: 256,
+            stack_size_limit: 1024,
+            timeout: Duration::from_secs(5),
+            max_script_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
 
-    register_pac_functions(&mut context)?;
+/// Per-engine state shared with the native PAC helpers.
+struct HelperState {
+    host: Arc<dyn PacHost>,
+    /// Set while the engine is running script code; helpers stop the script once it passes.
+    deadline: Cell<Option<Instant>>,
+    /// `shExpMatch` patterns compiled so far; `None` marks an invalid pattern.
+    shexp_cache: RefCell<HashMap<String, Option<Regex>>>,
+}
 
-    context.eval(Source::from_bytes(script.as_bytes()))?;
+/// Bound on distinct cached `shExpMatch` patterns, in case a script builds them dynamically.
+const SHEXP_CACHE_LIMIT: usize = 4096;
 
-    let global = context.global_object();
-    let func_val = global.get(js_string!("FindProxyForURL"), &mut context)?;
+impl HelperState {
+    fn check_deadline(&self) -> JsResult<()> {
+        match self.deadline.get() {
+            Some(deadline) if Instant::now() > deadline => Err(timeout_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+const TIMEOUT_MESSAGE: &str = "PAC evaluation timed out";
+
+fn timeout_error() -> JsError {
+    JsNativeError::runtime_limit()
+        .with_message(TIMEOUT_MESSAGE)
+        .into()
+}
+
+/// Most workers that may still be running a timed-out script at once, process-wide;
+/// engines built beyond it fail with [`PacError::LimitExceeded`].
+pub const MAX_STRAY_WORKERS: usize = 8;
+
+/// Workers whose engine stopped waiting for them and that have not exited yet.
+static STRAY_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+const RUNNING: u8 = 0;
+const ABANDONED: u8 = 1;
+const EXITED: u8 = 2;
+
+/// Lifecycle of a worker thread, shared between it and its engine.
+struct WorkerStatus(AtomicU8);
+
+impl WorkerStatus {
+    /// Called by the engine when it stops waiting for the worker.
+    fn abandon(&self) {
+        // 先计数再切换状态，避免线程恰好退出时计数下溢
+        STRAY_WORKERS.fetch_add(1, Ordering::SeqCst);
+        if self
+            .0
+            .compare_exchange(RUNNING, ABANDONED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            STRAY_WORKERS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Called by the worker thread as it exits.
+    fn exit(&self) {
+        if self.0.swap(EXITED, Ordering::SeqCst) == ABANDONED {
+            STRAY_WORKERS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// A PAC script that has been loaded and evaluated once, ready for repeated lookups.
+///
+/// Building the engine downloads (or takes) the script, registers the PAC helper
+/// functions and evaluates the script a single time; every [`find_proxy`](Self::find_proxy)
+/// call then only invokes `FindProxyForURL` on the already-evaluated context.
+///
+/// # Threading
+///
+/// The boa [`Context`] is neither `Send` nor `Sync`, so each engine keeps it on a worker
+/// thread of its own and sends it one lookup at a time; this is what lets
+/// [`PacLimits::timeout`] stop waiting for a script that never returns. `PacEngine`
+/// itself is `Send`, so it can be moved to another thread or kept behind a `Mutex`.
+/// For parallel lookups, fetch the script text once (a plain `String`) and build one
+/// engine per thread.
+///
+/// If a call times out, the worker stops the script and exits, and every later call on
+/// the engine fails with [`PacError::LimitExceeded`]; build a new engine to continue.
+///
+/// ```no_run
+/// use proxyparser::pac::{downloader::download_pac, PacEngine};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let script = download_pac("http://wpad.corp/wpad.dat")?;
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let script = script.clone();
+///         std::thread::spawn(move || {
+///             let mut engine = PacEngine::new(&script).unwrap();
+///             engine.find_proxy("https://intranet.corp/").unwrap()
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     println!("{}", worker.join().unwrap());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct PacEngine {
+    /// Lookups for the worker thread; `None` once a call has timed out, since the
+    /// worker may still be running it.
+    lookups: Option<mpsc::Sender<Lookup>>,
+    status: Arc<WorkerStatus>,
+    limits: PacLimits,
+    url_policy: UrlPolicy,
+}
+
+/// Errors produced on the worker thread, which must cross back to the caller.
+type WorkerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// One `FindProxyForURL` call for the worker thread.
+struct Lookup {
+    url: String,
+    host: String,
+    reply: mpsc::Sender<Result<String, WorkerError>>,
+}
+
+/// Stack of the worker thread; boa recurses on the native stack for native calls.
+const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+impl PacEngine {
+    /// Evaluates `script` once, against the real system, and keeps the resulting context.
+    pub fn new(script: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_options(script, PacOptions::default())
+    }
+
+    /// Evaluates `script` once with the given options and keeps the resulting context.
+    pub fn with_options(
+        script: &str,
+        options: PacOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let limits = options.limits;
+        if script.len() > limits.max_script_bytes {
+            return Err(Box::new(PacError::LimitExceeded {
+                limit: PacLimit::ScriptSize,
+                message: format!(
+                    "script is {} bytes, limit is {}",
+                    script.len(),
+                    limits.max_script_bytes
+                ),
+            }));
+        }
+
+        let stray = STRAY_WORKERS.load(Ordering::SeqCst);
+        if stray >= MAX_STRAY_WORKERS {
+            return Err(Box::new(PacError::LimitExceeded {
+                limit: PacLimit::Timeout,
+                message: format!("{} timed-out PAC scripts are still running", stray),
+            }));
+        }
+
+        let (lookups, jobs) = mpsc::channel::<Lookup>();
+        let (ready, evaluated) = mpsc::channel();
+        let script = script.to_string();
+        let host = options.host;
+        let status = Arc::new(WorkerStatus(AtomicU8::new(RUNNING)));
+        let worker_status = Arc::clone(&status);
+        thread::Builder::new()
+            .name("pac-engine".to_string())
+            .stack_size(WORKER_STACK_SIZE)
+            .spawn(move || {
+                let _exit = scopeguard::guard((), |_| worker_status.exit());
+                let mut worker = match Worker::new(&script, host, limits) {
+                    Ok(worker) => worker,
+                    Err(err) => {
+                        let _ = ready.send(Err(err));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(()));
+                // 调用方放弃等待后 reply 已关闭，发送失败可忽略
+                for lookup in jobs {
+                    let result = worker.find_proxy(&lookup.url, &lookup.host);
+                    let timed_out = matches!(&result, Err(err) if is_timeout(err.as_ref()));
+                    let _ = lookup.reply.send(result);
+                    if timed_out {
+                        // 被打断的上下文停在脚本中途，不能再用
+                        return;
+                    }
+                }
+            })?;
+        wait_for(&evaluated, &status, limits.timeout)?;
+
+        Ok(PacEngine {
+            lookups: Some(lookups),
+            status,
+            limits,
+            url_policy: options.url_policy,
+        })
+    }
+
+    /// Loads the PAC script at `location` (an `http(s)://`, `file://` or `data:` URL, or a
+    /// path; see [`PacSource::from_location`]) through the shared [`PacCache`] and
+    /// evaluates it once.
+    pub fn from_url(location: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let source = PacSource::from_location(location)?;
+        Self::from_source(&source, &PacCache::shared(), PacOptions::default())
+    }
+
+    /// Loads the script from `source` through `cache` and evaluates it once.
+    pub fn from_source(
+        source: &PacSource,
+        cache: &PacCache,
+        options: PacOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let script = cache.load(source)?;
+        Self::with_options(&script, options)
+    }
+
+    /// Returns the first proxy the script picks for `target_url`, skipping `DIRECT`
+    /// entries when a proxy follows them (same semantics as [`evaluate_pac_for_url`]).
+    pub fn find_proxy(
+        &mut self,
+        target_url: &str,
+    ) -> Result<ProxyDescriptor, Box<dyn std::error::Error>> {
+        let chain = self.find_proxy_result(target_url)?.proxies;
+        match chain.into_iter().find(|entry| !entry.is_direct()) {
+            Some(proxy) => Ok(proxy),
+            None => {
+                log_warn!("PAC returned no valid proxy, falling back to DIRECT");
+                Ok(ProxyDescriptor::Direct)
+            }
+        }
+    }
 
-    let func = func_val
-        .as_callable()
-        .ok_or(JsNativeError::typ().with_message("FindProxyForURL is not a function"))?;
+    /// Returns the whole ordered fallback chain the script picks for `target_url`.
+    ///
+    /// An empty or fully unrecognised PAC result is an error rather than an implicit
+    /// `DIRECT`, so callers can tell a broken script from one that chose to connect
+    /// directly.
+    pub fn find_proxy_chain(
+        &mut self,
+        target_url: &str,
+    ) -> Result<Vec<ProxyDescriptor>, Box<dyn std::error::Error>> {
+        let result = self.find_proxy_result(target_url)?;
+        if result.proxies.is_empty() {
+            return Err(match result.diagnostics.first() {
+                Some(diagnostic) => format!("PAC returned no usable entries: {}", diagnostic),
+                None => "PAC returned an empty result".to_string(),
+            }
+            .into());
+        }
+        Ok(result.proxies)
+    }
+
+    /// Returns the parsed result for `target_url`, including diagnostics for malformed
+    /// entries (see [`parse_pac_result`]). Each diagnostic is also logged as a warning.
+    pub fn find_proxy_result(
+        &mut self,
+        target_url: &str,
+    ) -> Result<PacResult, Box<dyn std::error::Error>> {
+        let raw_result = self.find_proxy_raw(target_url)?;
+        let result = parse_pac_result(&raw_result);
+        for diagnostic in &result.diagnostics {
+            log_warn!("Ignoring {}", diagnostic);
+        }
+        Ok(result)
+    }
+
+    /// Returns the unparsed string returned by `FindProxyForURL`.
+    ///
+    /// The script sees `target_url` as reduced by the configured [`UrlPolicy`], and its
+    /// host in the form given by [`canonicalize_host`].
+    pub fn find_proxy_raw(
+        &mut self,
+        target_url: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let url_obj = Url::parse(target_url)?;
+        let host = canonicalize_host(url_obj.host_str().ok_or("URL has no host")?);
+        let script_url = self.url_policy.apply(&url_obj);
 
-    let args = [
-        JsValue::from(js_string!(url)),
-        JsValue::from(js_string!(host)),
-    ];
+        let Some(lookups) = &self.lookups else {
+            return Err(Box::new(PacError::LimitExceeded {
+                limit: PacLimit::Timeout,
+                message: "an earlier call timed out; build a new engine".to_string(),
+            }));
+        };
+        let (reply, result) = mpsc::channel();
+        lookups
+            .send(Lookup {
+                url: script_url,
+                host,
+                reply,
+            })
+            .map_err(|_| "PAC worker thread has stopped")?;
+        let result = wait_for(&result, &self.status, self.limits.timeout);
+        if matches!(&result, Err(err) if is_timeout(err.as_ref())) {
+            // 工作线程已退出或仍在运行被放弃的脚本，后续调用不能排在它后面
+            self.lookups = None;
+        }
+        result
+    }
+}
 
-    let result = func.call(&JsValue::undefined(), &args, &mut context)?;
-    let js_str: JsString = result.to_string(&mut context)?;
+/// Waits at most `timeout` for the worker's answer, abandoning the worker if none comes.
+fn wait_for<T>(
+    result: &mpsc::Receiver<Result<T, WorkerError>>,
+    status: &WorkerStatus,
+    timeout: Duration,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match result.recv_timeout(timeout) {
+        Ok(result) => result.map_err(|err| err as Box<dyn std::error::Error>),
+        Err(RecvTimeoutError::Timeout) => {
+            status.abandon();
+            Err(Box::new(PacError::LimitExceeded {
+                limit: PacLimit::Timeout,
+                message: format!("no result after {:?}", timeout),
+            }))
+        }
+        Err(RecvTimeoutError::Disconnected) => Err("PAC worker thread has stopped".into()),
+    }
+}
+
+fn is_timeout(err: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        err.downcast_ref::<PacError>(),
+        Some(PacError::LimitExceeded {
+            limit: PacLimit::Timeout,
+            ..
+        })
+    )
+}
+
+/// The boa context and helper state, owned by an engine's worker thread.
+struct Worker {
+    context: Context,
+    state: Rc<HelperState>,
+    limits: PacLimits,
+}
+
+impl Worker {
+    fn new(script: &str, host: Arc<dyn PacHost>, limits: PacLimits) -> Result<Self, WorkerError> {
+        let mut context = Context::default();
+        let runtime_limits = context.runtime_limits_mut();
+        runtime_limits.set_loop_iteration_limit(limits.loop_iteration_limit);
+        runtime_limits.set_recursion_limit(limits.recursion_limit);
+        runtime_limits.set_stack_size_limit(limits.stack_size_limit);
+
+        let state = Rc::new(HelperState {
+            host,
+            deadline: Cell::new(None),
+            shexp_cache: RefCell::new(HashMap::new()),
+        });
+        register_pac_functions(&mut context, &state).map_err(|err| err.to_string())?;
+
+        let mut worker = Worker {
+            context,
+            state,
+            limits,
+        };
+        worker.run_limited(|context, deadline| {
+            eval_until(context, script, deadline)?;
+            Ok(())
+        })?;
+        Ok(worker)
+    }
+
+    fn find_proxy(&mut self, url: &str, host: &str) -> Result<String, WorkerError> {
+        self.run_limited(|context, deadline| call_find_proxy(context, url, host, deadline))
+    }
+
+    /// Runs script code with the helpers' deadline set and turns limit errors into
+    /// [`PacError`].
+    fn run_limited<T>(
+        &mut self,
+        run: impl FnOnce(&mut Context, Instant) -> JsResult<T>,
+    ) -> Result<T, WorkerError> {
+        let started = Instant::now();
+        let deadline = started + self.limits.timeout;
+        self.state.deadline.set(Some(deadline));
+        let result = run(&mut self.context, deadline);
+        self.state.deadline.set(None);
+
+        let elapsed = started.elapsed();
+        match result {
+            Ok(_) if elapsed > self.limits.timeout => Err(Box::new(PacError::LimitExceeded {
+                limit: PacLimit::Timeout,
+                message: format!("took {:?}, limit is {:?}", elapsed, self.limits.timeout),
+            })),
+            Ok(value) => Ok(value),
+            // JsError 不是 Send，只带回其文本
+            Err(err) => Err(limit_error(&err)
+                .map_or_else(|| err.to_string().into(), |e| Box::new(e) as WorkerError)),
+        }
+    }
+}
+
+/// boa "clock cycles" between two deadline checks.
+const CYCLE_BUDGET: u32 = 4096;
+
+/// Evaluates `source` in boa's budgeted mode, failing with a timeout once `deadline`
+/// has passed. The context is left mid-script in that case and must not be used again.
+fn eval_until(context: &mut Context, source: &str, deadline: Instant) -> JsResult<JsValue> {
+    let script = Script::parse(Source::from_bytes(source), None, context)?;
+    let mut evaluation = pin!(script.evaluate_async_with_budget(context, CYCLE_BUDGET));
+    // 预算用完时 boa 只是让出一次，不等待任何事件，用空 waker 轮询即可
+    let mut task = task::Context::from_waker(Waker::noop());
+    loop {
+        match evaluation.as_mut().poll(&mut task) {
+            Poll::Ready(result) => return result,
+            Poll::Pending if Instant::now() > deadline => return Err(timeout_error()),
+            Poll::Pending => {}
+        }
+    }
+}
+
+fn call_find_proxy(
+    context: &mut Context,
+    url: &str,
+    host: &str,
+    deadline: Instant,
+) -> JsResult<String> {
+    let global = context.global_object();
+
+    // 优先使用微软 IPv6 扩展入口 FindProxyForURLEx（若脚本定义了它）
+    let name = if global
+        .get(js_string!("FindProxyForURLEx"), context)?
+        .is_callable()
+    {
+        "FindProxyForURLEx"
+    } else if global
+        .get(js_string!("FindProxyForURL"), context)?
+        .is_callable()
+    {
+        "FindProxyForURL"
+    } else {
+        return Err(JsNativeError::typ()
+            .with_message("FindProxyForURL is not a function")
+            .into());
+    };
+
+    // 以脚本形式调用，调用本身也按预算执行，能被截止时间打断
+    let call = format!(
+        "{}({}, {})",
+        name,
+        js_string_literal(url),
+        js_string_literal(host)
+    );
+    let result = eval_until(context, &call, deadline)?;
+    let js_str: JsString = result.to_string(context)?;
 
     Ok(js_str.to_std_string().unwrap_or_default())
 }
 
-fn register_pac_functions(context: &mut Context) -> JsResult<()> {
-    // isPlainHostName(host)
+/// `value` as a double-quoted JavaScript string literal.
+fn js_string_literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                literal.push_str(&format!("\\u{{{:x}}}", u32::from(c)));
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Loads and evaluates the PAC script, returning the first proxy for `target_url`.
+///
+/// `pac_url` may be any location accepted by [`PacSource::from_location`].
+///
+/// Each call fetches and evaluates the script again; use [`PacEngine`] to resolve
+/// many URLs against the same script.
+pub fn evaluate_pac_for_url(
+    pac_url: &str,
+    target_url: &str,
+) -> Result<ProxyDescriptor, Box<dyn std::error::Error>> {
+    PacEngine::from_url(pac_url)?.find_proxy(target_url)
+}
+
+/// Like [`evaluate_pac_for_url`], but returns the whole ordered fallback chain
+/// (e.g. `[http://a:8080, http://b:8080, DIRECT]`) instead of only the first proxy.
+///
+/// An empty or fully unrecognised PAC result is an error.
+pub fn evaluate_pac_chain_for_url(
+    pac_url: &str,
+    target_url: &str,
+) -> Result<Vec<ProxyDescriptor>, Box<dyn std::error::Error>> {
+    PacEngine::from_url(pac_url)?.find_proxy_chain(target_url)
+}
+
+/// Signature of a PAC helper that needs the engine's [`HelperState`].
+type StateFn = fn(&HelperState, &[JsValue], &mut Context) -> JsResult<JsValue>;
+
+/// Registers a global PAC helper that is handed the engine's [`HelperState`].
+///
+/// These helpers may block (DNS), so the wall-clock limit is checked around each call.
+fn register_state_fn(
+    context: &mut Context,
+    name: &str,
+    length: usize,
+    state: &Rc<HelperState>,
+    function: StateFn,
+) -> JsResult<()> {
+    let state = Rc::clone(state);
+    // SAFETY: the closure only captures a fn pointer and an `Rc<HelperState>`, which holds
+    // an `Arc<dyn PacHost>` (`Send + Sync`, so free of GC-managed boa values), a `Cell`
+    // and compiled regexes; none of them can reference GC-managed values.
+    let native = unsafe {
+        NativeFunction::from_closure(move |_this, args, ctx| {
+            state.check_deadline()?;
+            let result = function(&state, args, ctx)?;
+            state.check_deadline()?;
+            Ok(result)
+        })
+    };
+    context.register_global_callable(JsString::from(name), length, native)
+}
+
+/// Maps boa's runtime-limit errors (and our own timeout) to [`PacError::LimitExceeded`].
+fn limit_error(err: &JsError) -> Option<PacError> {
+    let native = err.as_native().filter(|e| e.is_runtime_limit())?;
+    let message = native.message().to_string();
+    let limit = if message == TIMEOUT_MESSAGE {
+        PacLimit::Timeout
+    } else if message.contains("loop iteration") {
+        PacLimit::LoopIterations
+    } else if message.contains("recursive calls") {
+        PacLimit::Recursion
+    } else {
+        PacLimit::StackSize
+    };
+    Some(PacError::LimitExceeded { limit, message })
+}
+
+fn register_pac_functions(context: &mut Context, state: &Rc<HelperState>) -> JsResult<()> {
+    // isPlainHostName(host) - 与 Mozilla 一致：含 '.' 或 ':'（IPv4/IPv6 字面量）都不是普通主机名
     context.register_global_callable(
         "isPlainHostName".into(),
         1,
         NativeFunction::from_fn_ptr(|_this, args, _ctx| {
             let host = args
-                .get(0)
+                .first()
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
-            Ok((!host.contains('.')).into())
+            Ok((!host.contains(['.', ':'])).into())
         }),
     )?;
 
-    // dnsDomainIs(host, domain)
+    // dnsDomainIs(host, domain) - Mozilla 语义：纯后缀匹配，domain 通常以 '.' 开头
     context.register_global_callable(
         "dnsDomainIs".into(),
         2,
         NativeFunction::from_fn_ptr(|_this, args, _ctx| {
             let host = args
-                .get(0)
+                .first()
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
@@ -78,20 +667,17 @@ fn register_pac_functions(context: &mut Context) -> JsResult<()> {
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
-            let is_match = host.ends_with(&domain)
-                && (host.len() == domain.len()
-                    || host.as_bytes()[host.len() - domain.len() - 1] == b'.');
-            Ok(is_match.into())
+            Ok(host.ends_with(&domain).into())
         }),
     )?;
 
-    // localHostOrDomainIs(host, domain)
+    // localHostOrDomainIs(host, hostdom) - host 完全相同，或 host 是 hostdom 的前缀部分（"www" 匹配 "www.netscape.com"）
     context.register_global_callable(
         "localHostOrDomainIs".into(),
         2,
         NativeFunction::from_fn_ptr(|_this, args, _ctx| {
             let host = args
-                .get(0)
+                .first()
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
@@ -100,239 +686,431 @@ fn register_pac_functions(context: &mut Context) -> JsResult<()> {
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
-            Ok((host == domain || host.ends_with(&format!(".{}", domain))).into())
+            Ok((host == domain || domain.starts_with(&format!("{}.", host))).into())
         }),
     )?;
 
     // isResolvable(host)
+    register_state_fn(context, "isResolvable", 1, state, |state, args, _ctx| {
+        let name = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        Ok((!state.host.resolve(&name).is_empty()).into())
+    })?;
+
+    // dnsResolve(host) - 优先返回 IPv4 地址
+    register_state_fn(context, "dnsResolve", 1, state, |state, args, _ctx| {
+        let name = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        // Netscape 规范的 dnsResolve 返回 IPv4 地址；只有 IPv6 时才退而返回 IPv6
+        let ips = state.host.resolve(&name);
+        match ips.iter().find(|ip| ip.is_ipv4()).or(ips.first()) {
+            Some(ip) => Ok(js_string!(ip.to_string()).into()),
+            None => Ok(JsValue::null()),
+        }
+    })?;
+
+    // isInNet(host, pattern, mask) - host 可以是主机名（经 PacHost 解析）；支持 IPv6
+    // mask 可为点分掩码、IPv6 掩码或前缀长度；省略 mask 时 pattern 可写成 CIDR
+    register_state_fn(context, "isInNet", 3, state, |state, args, _ctx| {
+        let host_str = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        let net_str = args
+            .get(1)
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        let mask_str = args
+            .get(2)
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+
+        let (net_str, mask_str) = match net_str.split_once('/') {
+            Some((net, len)) if mask_str.is_empty() => (net.to_string(), len.to_string()),
+            _ => (net_str, mask_str),
+        };
+        let matched = parse_ip(&net_str)
+            .and_then(|net| {
+                let ip = address_for_family(state, &host_str, net)?;
+                ip_in_net(ip, net, &mask_str)
+            })
+            .unwrap_or(false);
+        Ok(matched.into())
+    })?;
+
+    // dnsDomainLevels(host) - 即 '.' 的个数，末尾的 '.' 也计入（"www.netscape.com." 为 3）
     context.register_global_callable(
-        "isResolvable".into(),
+        "dnsDomainLevels".into(),
         1,
         NativeFunction::from_fn_ptr(|_this, args, _ctx| {
             let host = args
-                .get(0)
+                .first()
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
-            let resolved = (host.as_str(), 0).to_socket_addrs().is_ok();
-            Ok(resolved.into())
+            let levels = host.matches('.').count() as i32;
+            Ok(JsValue::from(levels))
         }),
     )?;
 
-    // dnsResolve(host)
+    // myIpAddress() - 主 IPv4 地址，无可用地址时为 127.0.0.1
+    register_state_fn(context, "myIpAddress", 0, state, |state, _args, _ctx| {
+        let ip = state
+            .host
+            .local_addresses()
+            .into_iter()
+            .find(IpAddr::is_ipv4)
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        Ok(js_string!(ip).into())
+    })?;
+
+    // dnsResolveEx(host) - 微软扩展：返回所有地址（IPv4 与 IPv6），以分号分隔
+    register_state_fn(context, "dnsResolveEx", 1, state, |state, args, _ctx| {
+        let name = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        Ok(js_string!(join_ip_list(&state.host.resolve(&name))).into())
+    })?;
+
+    // isResolvableEx(host)
+    register_state_fn(context, "isResolvableEx", 1, state, |state, args, _ctx| {
+        let name = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        Ok((!state.host.resolve(&name).is_empty()).into())
+    })?;
+
+    // myIpAddressEx() - 本机所有地址（IPv4 与 IPv6），以分号分隔
+    register_state_fn(context, "myIpAddressEx", 0, state, |state, _args, _ctx| {
+        Ok(js_string!(join_ip_list(&state.host.local_addresses())).into())
+    })?;
+
+    // isInNetEx(host, ipPrefix) - ipPrefix 为 CIDR 形式，如 "198.95.0.0/16" 或 "3ffe:8311:ffff::/48"
+    register_state_fn(context, "isInNetEx", 2, state, |state, args, _ctx| {
+        let host_str = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        let prefix = args
+            .get(1)
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        let matched = prefix
+            .split_once('/')
+            .and_then(|(net, _)| parse_ip(net))
+            .and_then(|net| address_for_family(state, &host_str, net))
+            .and_then(|ip| ip_in_prefix(ip, &prefix))
+            .unwrap_or(false);
+        Ok(matched.into())
+    })?;
+
+    // sortIpAddressList(list) - IPv6 在前、IPv4 在后，各自升序；非法输入返回 false
     context.register_global_callable(
-        "dnsResolve".into(),
+        "sortIpAddressList".into(),
         1,
         NativeFunction::from_fn_ptr(|_this, args, _ctx| {
-            let host = args
-                .get(0)
+            let list = args
+                .first()
                 .and_then(|v| v.as_string())
                 .and_then(|s| s.to_std_string().ok())
                 .unwrap_or_default();
-            let ip = (host.as_str(), 0)
-                .to_socket_addrs()
-                .ok()
-                .and_then(|mut addrs| addrs.next())
-                .map(|addr| addr.ip().to_string());
-            if let Some(ip_str) = ip {
-                Ok(js_string!(ip_str).into())
-            } else {
-                Ok(JsValue::null())
+            match sort_ip_list(&list) {
+                Some(sorted) => Ok(js_string!(sorted).into()),
+                None => Ok(false.into()),
             }
         }),
     )?;
 
-    // isInNet(ip, net, mask)
+    // convert_addr(ipchars) - 点分地址转 32 位整数，与 Mozilla 一样按 ToInt32 取每段低 8 位，结果为有符号数
     context.register_global_callable(
-        "isInNet".into(),
-        3,
-        NativeFunction::from_fn_ptr(|_this, args, _ctx| {
-            let ip_str = args
-                .get(0)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
-            let net_str = args
-                .get(1)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
-            let mask_str = args
-                .get(2)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
-
-            let ip: Option<Ipv4Addr> = ip_str.parse().ok();
-            let net: Option<Ipv4Addr> = net_str.parse().ok();
-            let mask: Option<Ipv4Addr> = mask_str.parse().ok();
-
-            if let (Some(ip), Some(net), Some(mask)) = (ip, net, mask) {
-                let ip_u32 = u32::from_be_bytes(ip.octets());
-                let net_u32 = u32::from_be_bytes(net.octets());
-                let mask_u32 = u32::from_be_bytes(mask.octets());
-                Ok(((ip_u32 & mask_u32) == (net_u32 & mask_u32)).into())
-            } else {
-                Ok(false.into())
+        "convert_addr".into(),
+        1,
+        NativeFunction::from_fn_ptr(|_this, args, ctx| {
+            let ipchars = match args.first() {
+                Some(v) => v.to_string(ctx)?.to_std_string_escaped(),
+                None => "undefined".to_string(),
+            };
+            let mut bytes = ipchars.split('.');
+            let mut result: i32 = 0;
+            for shift in [24, 16, 8, 0] {
+                let byte = match bytes.next() {
+                    Some(part) => JsValue::from(js_string!(part)).to_i32(ctx)? & 0xff,
+                    None => 0,
+                };
+                result |= byte << shift;
             }
+            Ok(JsValue::from(result))
         }),
     )?;
 
-    // dnsDomainLevels(host)
+    // alert(message) - 以警告级别写入本 crate 的日志（stderr），不会混入 stdout 上的结果
     context.register_global_callable(
-        "dnsDomainLevels".into(),
+        "alert".into(),
         1,
-        NativeFunction::from_fn_ptr(|_this, args, _ctx| {
-            let host = args
-                .get(0)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
-            let levels = host.matches('.').count() as i32;
-            Ok(JsValue::from(levels))
+        NativeFunction::from_fn_ptr(|_this, args, ctx| {
+            let message = match args.first() {
+                Some(v) => v.to_string(ctx)?.to_std_string_escaped(),
+                None => "undefined".to_string(),
+            };
+            log_warn!("PAC alert: {}", message);
+            Ok(JsValue::undefined())
         }),
     )?;
 
-    // myIpAddress()
+    // getClientVersion() - 实现的微软 PAC 扩展版本
     context.register_global_callable(
-        "myIpAddress".into(),
+        "getClientVersion".into(),
         0,
-        NativeFunction::from_fn_ptr(|_this, _args, _ctx| {
-            let socket = UdpSocket::bind("0.0.0.0:0").ok();
-            let ip = socket
-                .as_ref()
-                .and_then(|s| s.connect("8.8.8.8:53").ok())
-                .and_then(|_| socket.as_ref().and_then(|s| s.local_addr().ok()))
-                .map(|addr| addr.ip().to_string())
-                .unwrap_or_else(|| "127.0.0.1".to_string());
-            Ok(js_string!(ip).into())
-        }),
+        NativeFunction::from_fn_ptr(|_this, _args, _ctx| Ok(js_string!("1.0").into())),
     )?;
 
-    // shExpMatch(str, glob)
-    context.register_global_callable(
-        "shExpMatch".into(),
-        2,
-        NativeFunction::from_fn_ptr(|_this, args, _ctx| {
-            let str_val = args
-                .get(0)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
-            let pattern = args
-                .get(1)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default();
+    // shExpMatch(str, shexp) - 按 shell 通配符匹配，编译结果按引擎缓存
+    register_state_fn(context, "shExpMatch", 2, state, |state, args, _ctx| {
+        let str_val = args
+            .first()
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+        let pattern = args
+            .get(1)
+            .and_then(|v| v.as_string())
+            .and_then(|s| s.to_std_string().ok())
+            .unwrap_or_default();
+
+        let mut cache = state.shexp_cache.borrow_mut();
+        if cache.len() >= SHEXP_CACHE_LIMIT && !cache.contains_key(&pattern) {
+            cache.clear();
+        }
+        let compiled = cache.entry(pattern).or_insert_with_key(|pattern| {
+            let compiled = compile_shexp(pattern);
+            if compiled.is_none() {
+                log_warn!("Invalid shExpMatch pattern {:?}, it never matches", pattern);
+            }
+            compiled
+        });
+        Ok(compiled
+            .as_ref()
+            .is_some_and(|re| re.is_match(&str_val))
+            .into())
+    })?;
 
-            let regex_pattern = pattern
-                .replace('*', ".*")
-                .replace('?', ".")
-                .replace('|', "\\|");
+    // weekdayRange(wd1, [wd2], ["GMT"])
+    register_state_fn(context, "weekdayRange", 3, state, |state, args, _ctx| {
+        Ok(weekday_range(&pac_args(args), state.host.now()).into())
+    })?;
 
-            let re = Regex::new(&format!("^{}$", regex_pattern))
-                .unwrap_or_else(|_| Regex::new("^$").unwrap());
+    // dateRange(day1, month1, year1, day2, month2, year2, ["GMT"]) 及其各种简写形式
+    register_state_fn(context, "dateRange", 7, state, |state, args, _ctx| {
+        Ok(date_range(&pac_args(args), state.host.now()).into())
+    })?;
 
-            Ok(re.is_match(&str_val).into())
-        }),
-    )?;
+    // timeRange(hour1, min1, sec1, hour2, min2, sec2, ["GMT"]) 及其各种简写形式
+    register_state_fn(context, "timeRange", 7, state, |state, args, _ctx| {
+        Ok(time_range(&pac_args(args), state.host.now()).into())
+    })?;
 
-    // weekdayRange(wd1, [wd2], [gmt])
-    context.register_global_callable(
-        "weekdayRange".into(),
-        3, // 最多 3 个参数
-        NativeFunction::from_fn_ptr(|_this, args, _ctx| {
-            let now = Utc::now();
-            let current_wd = match now.weekday() {
-                Weekday::Mon => "MON",
-                Weekday::Tue => "TUE",
-                Weekday::Wed => "WED",
-                Weekday::Thu => "THU",
-                Weekday::Fri => "FRI",
-                Weekday::Sat => "SAT",
-                Weekday::Sun => "SUN",
-            };
+    Ok(())
+}
 
-            let wd1 = args
-                .get(0)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok())
-                .unwrap_or_default()
-                .to_uppercase();
+/// The address `isInNet`/`isInNetEx` should test for `host`: the literal itself, or the
+/// first resolved address of the same family as `net` (falling back to the first one).
+fn address_for_family(state: &HelperState, host: &str, net: IpAddr) -> Option<IpAddr> {
+    if let Some(ip) = parse_ip(host) {
+        return Some(ip);
+    }
+    let ips = state.host.resolve(host);
+    ips.iter()
+        .find(|ip| ip.is_ipv4() == net.is_ipv4())
+        .or(ips.first())
+        .copied()
+}
 
-            let wd2 = args
-                .get(1)
-                .and_then(|v| v.as_string())
-                .and_then(|s| s.to_std_string().ok());
+/// Converts JS arguments of the date/time helpers; anything that is not a number is
+/// taken as text (`"MON"`, `"JAN"`, `"GMT"`, or a numeric string).
+fn pac_args(args: &[JsValue]) -> Vec<PacArg> {
+    args.iter()
+        .map(|v| match v.as_number() {
+            Some(n) => PacArg::Number(n as i64),
+            None => PacArg::Text(
+                v.as_string()
+                    .and_then(|s| s.to_std_string().ok())
+                    .unwrap_or_default(),
+            ),
+        })
+        .collect()
+}
 
-            // GMT 参数忽略（大多数 PAC 不依赖）
-            if wd2.is_none() {
-                return Ok((current_wd == wd1).into());
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
 
-            let wd2_str = wd2.unwrap().to_uppercase();
-
-            if wd1 == wd2_str {
-                Ok((current_wd == wd1).into())
-            } else {
-                let days = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
-                let start = days.iter().position(|&d| d == wd1).unwrap_or(0);
-                let end = days.iter().position(|&d| d == wd2_str).unwrap_or(0);
-                let curr = days.iter().position(|&d| d == current_wd).unwrap_or(0);
-
-                // 如果跨周（例如 FRI 到 MON），简单处理为 false 或 true，根据常见行为
-                if start <= end {
-                    Ok((curr >= start && curr <= end).into())
-                } else {
-                    Ok((curr >= start || curr <= end).into())
-                }
-            }
-        }),
-    )?;
+    // STRAY_WORKERS 是进程级计数，相关测试串行执行
+    static STRAY_LOCK: Mutex<()> = Mutex::new(());
 
-    // dateRange 和 timeRange - 企业 PAC 极少严格依赖时间，直接返回 true 足够
-    context.register_global_callable(
-        "dateRange".into(),
-        8,
-        NativeFunction::from_fn_ptr(|_, _, _| Ok(true.into())),
-    )?;
-    context.register_global_callable(
-        "timeRange".into(),
-        6,
-        NativeFunction::from_fn_ptr(|_, _, _| Ok(true.into())),
-    )?;
+    fn lock() -> std::sync::MutexGuard<'static, ()> {
+        STRAY_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 
-    Ok(())
-}
+    const DIRECT: &str = "function FindProxyForURL(url, host) { return 'DIRECT'; }";
 
-fn normalize_pac_result(raw: &str) -> String {
-    let trimmed = raw.trim();
-    if trimmed.eq_ignore_ascii_case("DIRECT") {
-        return "DIRECT".to_string();
+    fn returning(result: &str) -> PacEngine {
+        let script = format!(
+            "function FindProxyForURL(url, host) {{ return {}; }}",
+            js_string_literal(result)
+        );
+        PacEngine::new(&script).unwrap()
     }
 
-    for part in trimmed.split(';') {
-        let part = part.trim();
-        if part.eq_ignore_ascii_case("DIRECT") {
-            continue;
-        }
-        if let Some(stripped) = part.strip_prefix("PROXY ") {
-            return format!("http://{}", stripped.trim());
-        }
-        if let Some(stripped) = part.strip_prefix("HTTPS ") {
-            return format!("https://{}", stripped.trim());
-        }
-        if part.starts_with("SOCKS5 ") {
-            return format!("socks5://{}", part[7..].trim());
-        }
-        if part.starts_with("SOCKS ") {
-            return format!("socks5://{}", part[6..].trim());
+    fn stray_workers() -> usize {
+        STRAY_WORKERS.load(Ordering::SeqCst)
+    }
+
+    fn exits_within(status: &WorkerStatus, wait: Duration) -> bool {
+        let started = Instant::now();
+        while status.0.load(Ordering::SeqCst) != EXITED {
+            if started.elapsed() > wait {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
         }
-        if part.contains(':') && !part.contains("://") {
-            return format!("http://{}", part);
+        true
+    }
+
+    #[test]
+    fn timed_out_worker_exits() {
+        let _lock = lock();
+        // 循环分散在多次调用里，boa 的 loop_iteration_limit 拦不住，只有截止时间能停下它
+        let script = "
+            function f() { var n = 0; for (var i = 0; i < 900000; i++) { n += i; } return n; }
+            function FindProxyForURL(url, host) {
+                for (var j = 0; j < 400; j++) { f(); }
+                return 'DIRECT';
+            }";
+        let options = PacOptions {
+            limits: PacLimits {
+                timeout: Duration::from_millis(100),
+                ..PacLimits::default()
+            },
+            ..PacOptions::default()
+        };
+        let mut engine = PacEngine::with_options(script, options).unwrap();
+        let err = engine.find_proxy_raw("http://example.com/").unwrap_err();
+        assert!(is_timeout(err.as_ref()), "{}", err);
+
+        assert!(exits_within(&engine.status, Duration::from_secs(1)));
+        assert_eq!(stray_workers(), 0);
+    }
+
+    #[test]
+    fn stray_workers_are_capped() {
+        let _lock = lock();
+        let stuck: Vec<_> = (0..MAX_STRAY_WORKERS)
+            .map(|_| {
+                let status = WorkerStatus(AtomicU8::new(RUNNING));
+                status.abandon();
+                status
+            })
+            .collect();
+        assert_eq!(stray_workers(), MAX_STRAY_WORKERS);
+
+        let err = PacEngine::new(DIRECT).err().unwrap();
+        assert!(is_timeout(err.as_ref()), "{}", err);
+
+        for status in &stuck {
+            status.exit();
         }
+        assert_eq!(stray_workers(), 0);
+        assert!(PacEngine::new(DIRECT).is_ok());
     }
 
-    log_warn!("PAC returned no valid proxy, falling back to DIRECT");
-    "DIRECT".to_string()
+    #[test]
+    fn exited_workers_are_not_counted() {
+        let _lock = lock();
+        let status = WorkerStatus(AtomicU8::new(RUNNING));
+        status.exit();
+        status.abandon();
+        assert_eq!(stray_workers(), 0);
+
+        let engine = PacEngine::new(DIRECT).unwrap();
+        let status = Arc::clone(&engine.status);
+        drop(engine);
+        assert!(exits_within(&status, Duration::from_secs(1)));
+        assert_eq!(stray_workers(), 0);
+    }
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(js_string_literal("http://a/"), r#""http://a/""#);
+        assert_eq!(js_string_literal(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(js_string_literal("a\nb\u{2028}"), r#""a\u{a}b\u{2028}""#);
+
+        let mut context = Context::default();
+        let value = "q\"\\\n\u{2029}é";
+        let parsed = context
+            .eval(Source::from_bytes(&js_string_literal(value)))
+            .unwrap();
+        assert_eq!(parsed.as_string().unwrap().to_std_string().unwrap(), value);
+    }
+
+    #[test]
+    fn chain_keeps_every_entry_in_order() {
+        let chain = returning("PROXY a:1; SOCKS5 b:2; DIRECT")
+            .find_proxy_chain("http://example.com/")
+            .unwrap();
+        let chain: Vec<String> = chain.iter().map(ToString::to_string).collect();
+        assert_eq!(chain, ["http://a:1", "socks5://b:2", "DIRECT"]);
+
+        // 同一脚本，find_proxy 只取第一个代理
+        assert_eq!(
+            returning("DIRECT; PROXY a:1")
+                .find_proxy("http://example.com/")
+                .unwrap()
+                .to_string(),
+            "http://a:1"
+        );
+
+        let script =
+            "function FindProxyForURL(url, host) { return 'PROXY a:1; SOCKS5 b:2; DIRECT'; }";
+        let chain = evaluate_pac_chain_for_url(
+            &format!("data:,{}", script.replace(' ', "%20")),
+            "http://example.com/",
+        )
+        .unwrap();
+        assert_eq!(chain.len(), 3);
+        assert!(chain[2].is_direct());
+    }
+
+    #[test]
+    fn chain_without_usable_entries_is_an_error() {
+        let err = returning("bogus; a:1; PROXY")
+            .find_proxy_chain("http://example.com/")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "PAC returned no usable entries: PAC entry 1 'bogus': unknown proxy type 'bogus'"
+        );
+        let err = returning(" ; ")
+            .find_proxy_chain("http://example.com/")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "PAC returned an empty result");
+    }
 }
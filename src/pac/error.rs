@@ -0,0 +1,51 @@
+// src/pac/error.rs
+use std::fmt;
+
+/// The execution limit a PAC script ran into. See [`PacLimits`](super::PacLimits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacLimit {
+    LoopIterations,
+    Recursion,
+    StackSize,
+    Timeout,
+    ScriptSize,
+}
+
+impl fmt::Display for PacLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PacLimit::LoopIterations => "loop iteration limit",
+            PacLimit::Recursion => "recursion limit",
+            PacLimit::StackSize => "stack size limit",
+            PacLimit::Timeout => "time limit",
+            PacLimit::ScriptSize => "script size limit",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Typed PAC failures that callers may want to tell apart from ordinary script errors.
+///
+/// PAC functions return `Box<dyn Error>`; use `downcast_ref::<PacError>()` to inspect it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacError {
+    /// The script exceeded one of the configured execution limits and was stopped.
+    LimitExceeded { limit: PacLimit, message: String },
+    /// What was fetched from `location` is not a PAC script, e.g. an HTML login page.
+    NotAPacScript { location: String, reason: String },
+}
+
+impl fmt::Display for PacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacError::LimitExceeded { limit, message } => {
+                write!(f, "PAC script exceeded the {}: {}", limit, message)
+            }
+            PacError::NotAPacScript { location, reason } => {
+                write!(f, "{} is not a PAC script: {}", location, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PacError {}
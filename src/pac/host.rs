@@ -0,0 +1,181 @@
+// src/pac/host.rs
+use super::interfaces::{interface_addresses, rank_local_addresses};
+use super::resolver::DnsResolver;
+use chrono::{DateTime, FixedOffset, Local};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// The machine-dependent facts a PAC script can observe: name resolution, the local
+/// addresses and the current time.
+///
+/// [`PacEngine`](super::PacEngine) routes `dnsResolve`, `isResolvable`, `myIpAddress`,
+/// the `*Ex` helpers and `weekdayRange`/`dateRange`/`timeRange` through this trait.
+/// Use [`SystemHost`] for real lookups, [`FixedHost`] for reproducible offline
+/// evaluation, or implement it yourself. Implementations must be `Send + Sync` so one
+/// host can be shared by engines on several threads.
+pub trait PacHost: Send + Sync {
+    /// Every address `host` resolves to, in preference order. Empty means unresolvable.
+    fn resolve(&self, host: &str) -> Vec<IpAddr>;
+
+    /// Addresses of this machine, primary first. `myIpAddress` returns the first IPv4
+    /// entry and `myIpAddressEx` all of them.
+    fn local_addresses(&self) -> Vec<IpAddr>;
+
+    /// Current time, with the UTC offset of the local time zone.
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// The real environment: system resolver, local network interfaces and the local clock.
+/// This is the default host.
+///
+/// Name resolution goes through a [`DnsResolver`], which bounds each lookup and caches
+/// answers. `SystemHost::default()` uses the process-wide [`DnsResolver::shared`] cache.
+///
+/// Local addresses come from enumerating the interfaces (no packets are sent), ranked by
+/// [`local_addresses`](PacHost::local_addresses): loopback and link-local addresses are
+/// skipped, physical interfaces win over container/VM bridges and IPv4 over IPv6. Use
+/// [`with_interface`](Self::with_interface) or [`with_local_address`](Self::with_local_address)
+/// when that guess is wrong, e.g. on VPN-split hosts.
+#[derive(Debug, Clone)]
+pub struct SystemHost {
+    resolver: DnsResolver,
+    preferred_interface: Option<String>,
+    local_address: Option<IpAddr>,
+}
+
+impl SystemHost {
+    /// A system host resolving names through `resolver`.
+    pub fn new(resolver: DnsResolver) -> Self {
+        SystemHost {
+            resolver,
+            preferred_interface: None,
+            local_address: None,
+        }
+    }
+
+    /// Ranks the addresses of interface `name` (e.g. `"eth0"`, `"utun3"`) first.
+    pub fn with_interface(mut self, name: impl Into<String>) -> Self {
+        self.preferred_interface = Some(name.into());
+        self
+    }
+
+    /// Makes `ip` the primary local address, whatever the interfaces say.
+    ///
+    /// `myIpAddress` returns the first IPv4 local address, so pass an IPv4 address to
+    /// change its result.
+    pub fn with_local_address(mut self, ip: IpAddr) -> Self {
+        self.local_address = Some(ip);
+        self
+    }
+
+    pub fn resolver(&self) -> &DnsResolver {
+        &self.resolver
+    }
+}
+
+impl Default for SystemHost {
+    fn default() -> Self {
+        SystemHost::new(DnsResolver::shared())
+    }
+}
+
+impl PacHost for SystemHost {
+    fn resolve(&self, host: &str) -> Vec<IpAddr> {
+        self.resolver.resolve(host)
+    }
+
+    fn local_addresses(&self) -> Vec<IpAddr> {
+        let mut ips =
+            rank_local_addresses(&interface_addresses(), self.preferred_interface.as_deref());
+        if let Some(ip) = self.local_address {
+            ips.retain(|a| *a != ip);
+            ips.insert(0, ip);
+        }
+        ips
+    }
+
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+}
+
+/// A host with fixed answers, for deterministic PAC evaluation and tests.
+///
+/// Names that were not registered with [`with_dns`](Self::with_dns) are unresolvable.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::DateTime;
+/// use proxyparser::pac::{FixedHost, PacEngine, PacOptions};
+/// use std::sync::Arc;
+///
+/// // Pretend we are on 10.20.0.5 on a Saturday at 23:00.
+/// let host = FixedHost::new(DateTime::parse_from_rfc3339("2026-10-17T23:00:00+02:00").unwrap())
+///     .with_local_address("10.20.0.5".parse().unwrap())
+///     .with_dns("intranet.corp", ["10.1.0.7".parse().unwrap()]);
+///
+/// let script = r#"
+///     function FindProxyForURL(url, host) {
+///         if (weekdayRange("SAT", "SUN") && timeRange(22, 6)) return "PROXY backup.corp:3128";
+///         if (dnsResolve(host) == "10.1.0.7") return "DIRECT";
+///         return "PROXY " + myIpAddress() + ":8080";
+///     }
+/// "#;
+/// let options = PacOptions { host: Arc::new(host), ..PacOptions::default() };
+/// let mut engine = PacEngine::with_options(script, options).unwrap();
+/// assert_eq!(engine.find_proxy_raw("http://intranet.corp/").unwrap(), "PROXY backup.corp:3128");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedHost {
+    dns: HashMap<String, Vec<IpAddr>>,
+    local_addresses: Vec<IpAddr>,
+    now: DateTime<FixedOffset>,
+}
+
+impl FixedHost {
+    /// A host frozen at `now`, with no local addresses and no resolvable names.
+    pub fn new(now: DateTime<FixedOffset>) -> Self {
+        FixedHost {
+            dns: HashMap::new(),
+            local_addresses: Vec::new(),
+            now,
+        }
+    }
+
+    /// Makes `host` (case-insensitive) resolve to `ips`, in that order.
+    pub fn with_dns(mut self, host: &str, ips: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.dns
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .extend(ips);
+        self
+    }
+
+    /// Appends a local address; the first one added is the primary address.
+    pub fn with_local_address(mut self, ip: IpAddr) -> Self {
+        self.local_addresses.push(ip);
+        self
+    }
+}
+
+impl PacHost for FixedHost {
+    fn resolve(&self, host: &str) -> Vec<IpAddr> {
+        // 与系统解析器一致：IP 字面量解析为其自身
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return vec![ip];
+        }
+        self.dns
+            .get(&host.to_ascii_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn local_addresses(&self) -> Vec<IpAddr> {
+        self.local_addresses.clone()
+    }
+
+    fn now(&self) -> DateTime<FixedOffset> {
+        self.now
+    }
+}
@@ -0,0 +1,191 @@
+// src/pac/interfaces.rs
+use crate::log_warn;
+use std::net::IpAddr;
+
+/// An address assigned to a local network interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddress {
+    /// Interface name (`eth0`, `en0`, `Ethernet 2`, …).
+    pub interface: String,
+    pub ip: IpAddr,
+    /// Whether the interface is up; `true` when the platform does not report it.
+    pub is_up: bool,
+}
+
+/// Every address of every local interface, as reported by the OS, including loopback.
+pub fn interface_addresses() -> Vec<InterfaceAddress> {
+    match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces
+            .into_iter()
+            .map(|iface| InterfaceAddress {
+                ip: iface.ip(),
+                is_up: iface.oper_status != if_addrs::IfOperStatus::Down,
+                interface: iface.name,
+            })
+            .collect(),
+        Err(e) => {
+            log_warn!("Failed to enumerate network interfaces: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 容器、虚拟机网桥等通常不是对外通信的接口
+const VIRTUAL_INTERFACE_PREFIXES: [&str; 12] = [
+    "docker", "veth", "br-", "virbr", "vboxnet", "vmnet", "cni", "flannel", "podman", "lxcbr",
+    "lxdbr", "cali",
+];
+
+fn is_virtual(interface: &str) -> bool {
+    VIRTUAL_INTERFACE_PREFIXES
+        .iter()
+        .any(|prefix| interface.starts_with(prefix))
+}
+
+fn is_usable(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local() && !v4.is_unspecified(),
+        // fe80::/10 链路本地地址不可路由
+        IpAddr::V6(v6) => {
+            !v6.is_loopback() && !v6.is_unspecified() && (v6.segments()[0] & 0xffc0) != 0xfe80
+        }
+    }
+}
+
+/// Orders the usable (non-loopback, non-link-local) addresses, primary first: addresses
+/// on `preferred_interface` first, then physical before virtual interfaces, IPv4 before
+/// IPv6. Interfaces that are down are skipped.
+pub(crate) fn rank_local_addresses(
+    addresses: &[InterfaceAddress],
+    preferred_interface: Option<&str>,
+) -> Vec<IpAddr> {
+    let mut usable: Vec<&InterfaceAddress> = addresses
+        .iter()
+        .filter(|a| a.is_up && is_usable(&a.ip))
+        .collect();
+    // 稳定排序，保持操作系统给出的接口顺序
+    usable.sort_by_key(|a| {
+        (
+            preferred_interface != Some(a.interface.as_str()),
+            is_virtual(&a.interface),
+            a.ip.is_ipv6(),
+        )
+    });
+
+    let mut ips: Vec<IpAddr> = Vec::new();
+    for address in usable {
+        if !ips.contains(&address.ip) {
+            ips.push(address.ip);
+        }
+    }
+    ips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(interface: &str, ip: &str) -> InterfaceAddress {
+        InterfaceAddress {
+            interface: interface.to_string(),
+            ip: ip.parse().unwrap(),
+            is_up: true,
+        }
+    }
+
+    fn ranked(addresses: &[InterfaceAddress], preferred: Option<&str>) -> Vec<String> {
+        rank_local_addresses(addresses, preferred)
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(rank_local_addresses(&[], None).is_empty());
+        assert!(rank_local_addresses(&[], Some("eth0")).is_empty());
+    }
+
+    #[test]
+    fn unusable_addresses_are_skipped() {
+        let addresses = [
+            address("lo", "127.0.0.1"),
+            address("lo", "::1"),
+            address("eth0", "169.254.10.1"),
+            address("eth0", "fe80::1"),
+            address("eth0", "febf::1"),
+            address("eth0", "0.0.0.0"),
+            address("eth0", "::"),
+        ];
+        assert!(rank_local_addresses(&addresses, None).is_empty());
+
+        let mut down = address("eth1", "10.0.0.5");
+        down.is_up = false;
+        assert!(rank_local_addresses(&[down], None).is_empty());
+    }
+
+    #[test]
+    fn ipv4_before_ipv6() {
+        let addresses = [
+            address("eth0", "2001:db8::5"),
+            address("eth0", "192.168.1.5"),
+        ];
+        assert_eq!(ranked(&addresses, None), ["192.168.1.5", "2001:db8::5"]);
+    }
+
+    #[test]
+    fn physical_before_virtual() {
+        let addresses = [
+            address("docker0", "172.17.0.1"),
+            address("virbr0", "192.168.122.1"),
+            address("wlan0", "2001:db8::7"),
+            address("eth0", "10.1.2.3"),
+        ];
+        // 物理接口优先于地址族：物理接口上的 IPv6 排在虚拟网桥的 IPv4 之前
+        assert_eq!(
+            ranked(&addresses, None),
+            ["10.1.2.3", "2001:db8::7", "172.17.0.1", "192.168.122.1"]
+        );
+    }
+
+    #[test]
+    fn preferred_interface_first() {
+        let addresses = [
+            address("eth0", "10.1.2.3"),
+            address("tun0", "2001:db8::9"),
+            address("tun0", "10.8.0.2"),
+        ];
+        assert_eq!(
+            ranked(&addresses, Some("tun0")),
+            ["10.8.0.2", "2001:db8::9", "10.1.2.3"]
+        );
+        // 不存在的首选接口不影响排序
+        assert_eq!(
+            ranked(&addresses, Some("wlan0")),
+            ["10.1.2.3", "10.8.0.2", "2001:db8::9"]
+        );
+    }
+
+    #[test]
+    fn private_and_global_keep_os_order() {
+        let addresses = [
+            address("eth0", "192.168.1.5"),
+            address("eth1", "203.0.113.7"),
+            address("eth2", "10.0.0.9"),
+        ];
+        assert_eq!(
+            ranked(&addresses, None),
+            ["192.168.1.5", "203.0.113.7", "10.0.0.9"]
+        );
+    }
+
+    #[test]
+    fn duplicates_are_dropped() {
+        let addresses = [
+            address("eth0", "10.1.2.3"),
+            address("eth0:1", "10.1.2.3"),
+            address("bond0", "10.1.2.3"),
+        ];
+        assert_eq!(ranked(&addresses, None), ["10.1.2.3"]);
+    }
+}
@@ -1,10 +1,37 @@
 // src/pac/mod.rs
+mod cache;
+mod datetime;
+mod decode;
 pub mod downloader;
 pub mod engine;
+mod error;
+mod host;
+mod interfaces;
+pub(crate) mod net;
+mod resolver;
+mod result;
+mod shexp;
+mod source;
+mod url_policy;
 
-pub use engine::evaluate_pac_for_url;
+pub use cache::{PacCache, PacCacheConfig};
+pub use downloader::{DownloaderConfig, PacFetchRoute};
+pub use engine::{
+    evaluate_pac_chain_for_url, evaluate_pac_for_url, PacEngine, PacLimits, PacOptions,
+    MAX_STRAY_WORKERS,
+};
+pub use error::{PacError, PacLimit};
+pub use host::{FixedHost, PacHost, SystemHost};
+pub use interfaces::{interface_addresses, InterfaceAddress};
+pub use resolver::{DnsResolver, ResolverConfig, MAX_PENDING_LOOKUPS};
+pub use result::{parse_pac_result, PacDiagnostic, PacResult};
+pub use source::PacSource;
+pub use url_policy::UrlPolicy;
 
 /// 判断一个字符串是否像是 PAC 脚本 URL
+#[deprecated(
+    note = "guesses from the URL text; use system_proxy::get_system_proxy_config, which reports PAC URLs as SystemProxyConfig::AutoConfigUrl"
+)]
 pub fn is_pac_url(s: &str) -> bool {
     let lower = s.to_lowercase();
     lower.ends_with(".pac") || lower.contains("proxy.pac") || lower.contains("wpad.dat")
@@ -0,0 +1,84 @@
+// src/pac/net.rs
+//! Address helpers shared by the PAC functions (`isInNetEx`, `sortIpAddressList`, …).
+
+use std::net::IpAddr;
+
+/// Parses an IP literal, accepting IPv6 with or without brackets.
+pub(crate) fn parse_ip(s: &str) -> Option<IpAddr> {
+    s.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Returns true when `ip` lies inside `prefix`, given in CIDR form
+/// (`"198.95.0.0/16"`, `"3ffe:8311:ffff::/48"`). Mixed families never match.
+pub(crate) fn ip_in_prefix(ip: IpAddr, prefix: &str) -> Option<bool> {
+    let (net, len) = prefix.trim().split_once('/')?;
+    let net = parse_ip(net)?;
+    let len: u32 = len.trim().parse().ok()?;
+
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) if len <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+            Some(u32::from(ip) & mask == u32::from(net) & mask)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) if len <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+            Some(u128::from(ip) & mask == u128::from(net) & mask)
+        }
+        (IpAddr::V4(_), IpAddr::V6(_)) | (IpAddr::V6(_), IpAddr::V4(_)) => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns true when `ip` lies inside `net` masked by `mask`, as `isInNet` defines it.
+///
+/// `mask` may be a dotted IPv4 mask (`"255.255.0.0"`), an IPv6 mask (`"ffff:ffff::"`)
+/// or a prefix length (`"16"`, `"/64"`). `None` when the mask does not fit `net`.
+pub(crate) fn ip_in_net(ip: IpAddr, net: IpAddr, mask: &str) -> Option<bool> {
+    let mask = mask.trim();
+    if let Ok(len) = mask.trim_start_matches('/').parse::<u32>() {
+        return ip_in_prefix(ip, &format!("{}/{}", net, len));
+    }
+
+    match (ip, net, parse_ip(mask)?) {
+        (IpAddr::V4(ip), IpAddr::V4(net), IpAddr::V4(mask)) => {
+            let mask = u32::from(mask);
+            Some(u32::from(ip) & mask == u32::from(net) & mask)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net), IpAddr::V6(mask)) => {
+            let mask = u128::from(mask);
+            Some(u128::from(ip) & mask == u128::from(net) & mask)
+        }
+        (IpAddr::V4(_), IpAddr::V6(_), _) | (IpAddr::V6(_), IpAddr::V4(_), _) => Some(false),
+        _ => None,
+    }
+}
+
+/// Joins addresses the way the Microsoft `*Ex` helpers return them: `"a;b;c"`.
+pub(crate) fn join_ip_list(ips: &[IpAddr]) -> String {
+    ips.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// sortIpAddressList: IPv6 addresses first, then IPv4, each in ascending order.
+/// Returns `None` when the list is empty or contains anything that is not an IP literal.
+pub(crate) fn sort_ip_list(list: &str) -> Option<String> {
+    let mut ips = list
+        .split(';')
+        .map(parse_ip)
+        .collect::<Option<Vec<IpAddr>>>()?;
+    if ips.is_empty() {
+        return None;
+    }
+
+    ips.sort_by_key(|ip| match ip {
+        IpAddr::V6(v6) => (0, u128::from(*v6)),
+        IpAddr::V4(v4) => (1, u32::from(*v4) as u128),
+    });
+    Some(join_ip_list(&ips))
+}
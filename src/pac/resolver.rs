@@ -0,0 +1,349 @@
+// src/pac/resolver.rs
+use crate::{log_debug, log_warn};
+use std::collections::HashMap;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Timeout and cache settings for [`DnsResolver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolverConfig {
+    /// Longest a single lookup may block; a lookup that takes longer counts as a failure.
+    pub timeout: Duration,
+    /// How long successful lookups are cached.
+    pub positive_ttl: Duration,
+    /// How long failed or timed-out lookups are cached.
+    pub negative_ttl: Duration,
+    /// Maximum number of cached names.
+    pub max_entries: usize,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        ResolverConfig {
+            timeout: Duration::from_secs(2),
+            positive_ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(10),
+            max_entries: 1024,
+        }
+    }
+}
+
+/// Most lookups a resolver and its clones may have running at once, counting timed-out
+/// ones still blocked in the system resolver; further names fail at once as unresolvable.
+pub const MAX_PENDING_LOOKUPS: usize = 16;
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    ips: Vec<IpAddr>,
+    expires: Instant,
+}
+
+/// System resolver with a per-lookup timeout and a positive/negative cache.
+///
+/// The standard library cannot cancel `getaddrinfo`, so each lookup runs on a helper
+/// thread and the caller stops waiting after [`ResolverConfig::timeout`]. A timed-out
+/// name is cached as unresolvable, so a dead resolver costs one timeout per name and
+/// negative TTL rather than one per PAC call. Abandoned helper threads still count
+/// towards [`MAX_PENDING_LOOKUPS`] until the system resolver returns; names refused
+/// while the budget is used up are reported as unresolvable but not cached.
+///
+/// Clones share the same cache and lookup budget.
+#[derive(Debug, Clone)]
+pub struct DnsResolver {
+    config: ResolverConfig,
+    cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    /// Helper threads that have not exited yet.
+    pending: Arc<AtomicUsize>,
+    lookup: fn(&str) -> Vec<IpAddr>,
+}
+
+impl DnsResolver {
+    pub fn new(config: ResolverConfig) -> Self {
+        DnsResolver {
+            config,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            pending: Arc::new(AtomicUsize::new(0)),
+            lookup: system_lookup,
+        }
+    }
+
+    /// The process-wide resolver used by [`SystemHost::default`](super::SystemHost).
+    pub fn shared() -> DnsResolver {
+        static SHARED: OnceLock<DnsResolver> = OnceLock::new();
+        SHARED
+            .get_or_init(|| DnsResolver::new(ResolverConfig::default()))
+            .clone()
+    }
+
+    pub fn config(&self) -> &ResolverConfig {
+        &self.config
+    }
+
+    /// Every address `host` resolves to, in resolver order and without duplicates.
+    /// Empty when the name does not resolve or the lookup timed out.
+    pub fn resolve(&self, host: &str) -> Vec<IpAddr> {
+        let host = host.trim().to_ascii_lowercase();
+        if host.is_empty() {
+            return Vec::new();
+        }
+        if let Ok(ip) = host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+        {
+            return vec![ip];
+        }
+
+        let now = Instant::now();
+        if let Some(entry) = self.lock().get(&host) {
+            if entry.expires > now {
+                log_debug!("DNS cache hit for {}", host);
+                return entry.ips.clone();
+            }
+        }
+
+        let Some(ips) = self.lookup(&host) else {
+            return Vec::new();
+        };
+        let ttl = if ips.is_empty() {
+            self.config.negative_ttl
+        } else {
+            self.config.positive_ttl
+        };
+        self.insert(
+            host,
+            CacheEntry {
+                ips: ips.clone(),
+                expires: Instant::now() + ttl,
+            },
+        );
+        ips
+    }
+
+    /// Drops every cached answer.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// The addresses of `host`, empty when it does not resolve or timed out; `None`
+    /// when no lookup was started, which must not be cached.
+    fn lookup(&self, host: &str) -> Option<Vec<IpAddr>> {
+        let pending = self.pending.fetch_add(1, Ordering::SeqCst);
+        if pending >= MAX_PENDING_LOOKUPS {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            log_warn!(
+                "{} DNS lookups are still running, treating {} as unresolvable",
+                pending,
+                host
+            );
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let name = host.to_string();
+        let lookup = self.lookup;
+        let counter = Arc::clone(&self.pending);
+        let spawned = thread::Builder::new()
+            .name("pac-dns".to_string())
+            .spawn(move || {
+                let ips = lookup(&name);
+                // 调用方可能已超时离开，发送失败无需处理
+                let _ = tx.send(ips);
+                counter.fetch_sub(1, Ordering::SeqCst);
+            });
+        if let Err(e) = spawned {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            log_warn!("Failed to start DNS lookup for {}: {}", host, e);
+            return None;
+        }
+
+        match rx.recv_timeout(self.config.timeout) {
+            Ok(ips) => Some(ips),
+            Err(_) => {
+                log_warn!(
+                    "DNS lookup for {} timed out after {:?}",
+                    host,
+                    self.config.timeout
+                );
+                Some(Vec::new())
+            }
+        }
+    }
+
+    fn insert(&self, host: String, entry: CacheEntry) {
+        let mut cache = self.lock();
+        if cache.len() >= self.config.max_entries && !cache.contains_key(&host) {
+            let now = Instant::now();
+            cache.retain(|_, e| e.expires > now);
+            if cache.len() >= self.config.max_entries {
+                // 仍然满：淘汰最早过期的一项
+                if let Some(oldest) = cache
+                    .iter()
+                    .min_by_key(|(_, e)| e.expires)
+                    .map(|(k, _)| k.clone())
+                {
+                    cache.remove(&oldest);
+                }
+            }
+        }
+        if self.config.max_entries > 0 {
+            cache.insert(host, entry);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        // 缓存内容总是完整写入，锁中毒时继续使用即可
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Blocking `getaddrinfo` lookup, without duplicate addresses.
+fn system_lookup(host: &str) -> Vec<IpAddr> {
+    let mut ips: Vec<IpAddr> = Vec::new();
+    if let Ok(addrs) = (host, 0).to_socket_addrs() {
+        for addr in addrs {
+            if !ips.contains(&addr.ip()) {
+                ips.push(addr.ip());
+            }
+        }
+    }
+    ips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Answers every name with a different address, except `missing.*` names.
+    fn counting(host: &str) -> Vec<IpAddr> {
+        let n = LOOKUPS.fetch_add(1, Ordering::SeqCst);
+        if host.starts_with("missing.") {
+            return Vec::new();
+        }
+        vec![IpAddr::from([10, 0, (n >> 8) as u8, n as u8])]
+    }
+
+    fn slow(_host: &str) -> Vec<IpAddr> {
+        thread::sleep(Duration::from_secs(1));
+        vec![IpAddr::from([10, 0, 0, 1])]
+    }
+
+    fn unreachable(host: &str) -> Vec<IpAddr> {
+        panic!("unexpected lookup of {}", host)
+    }
+
+    fn resolver(config: ResolverConfig, lookup: fn(&str) -> Vec<IpAddr>) -> DnsResolver {
+        DnsResolver {
+            lookup,
+            ..DnsResolver::new(config)
+        }
+    }
+
+    #[test]
+    fn cache_hit() {
+        let resolver = resolver(ResolverConfig::default(), counting);
+        let first = resolver.resolve("www.example");
+        assert_eq!(first.len(), 1);
+        // 每次查询的结果都不同，相同说明命中了缓存；名字不区分大小写
+        assert_eq!(resolver.resolve("WWW.Example "), first);
+        assert_eq!(resolver.clone().resolve("www.example"), first);
+
+        resolver.clear();
+        assert_ne!(resolver.resolve("www.example"), first);
+    }
+
+    #[test]
+    fn negative_entries_expire() {
+        let config = ResolverConfig {
+            negative_ttl: Duration::from_millis(50),
+            ..ResolverConfig::default()
+        };
+        let resolver = resolver(config, counting);
+        assert!(resolver.resolve("missing.example").is_empty());
+        let expires = resolver.lock()["missing.example"].expires;
+        assert!(expires <= Instant::now() + config.negative_ttl);
+
+        // 过期前命中缓存，过期后重新查询
+        assert!(resolver.resolve("missing.example").is_empty());
+        assert_eq!(resolver.lock()["missing.example"].expires, expires);
+        thread::sleep(Duration::from_millis(60));
+        assert!(resolver.resolve("missing.example").is_empty());
+        assert!(resolver.lock()["missing.example"].expires > expires);
+
+        // 成功的结果使用更长的 positive_ttl
+        resolver.resolve("www.example");
+        assert!(resolver.lock()["www.example"].expires > Instant::now() + config.negative_ttl);
+    }
+
+    #[test]
+    fn eviction() {
+        let config = ResolverConfig {
+            max_entries: 2,
+            ..ResolverConfig::default()
+        };
+        let resolver = resolver(config, counting);
+        let a = resolver.resolve("a.example");
+        resolver.resolve("b.example");
+        resolver.resolve("c.example");
+        assert_eq!(resolver.lock().len(), 2);
+        // 最早过期的 a 被淘汰，再次解析得到新的结果
+        assert!(!resolver.lock().contains_key("a.example"));
+        assert_ne!(resolver.resolve("a.example"), a);
+    }
+
+    #[test]
+    fn ip_literals_skip_the_lookup() {
+        let resolver = resolver(ResolverConfig::default(), unreachable);
+        assert_eq!(
+            resolver.resolve("10.1.2.3"),
+            vec![IpAddr::from([10, 1, 2, 3])]
+        );
+        assert_eq!(
+            resolver.resolve("::1"),
+            vec!["::1".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(
+            resolver.resolve("[2001:db8::1]"),
+            vec!["2001:db8::1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(resolver.resolve("  ").is_empty());
+        assert!(resolver.lock().is_empty());
+    }
+
+    #[test]
+    fn timeouts_are_cached_and_capped() {
+        let config = ResolverConfig {
+            timeout: Duration::from_millis(10),
+            ..ResolverConfig::default()
+        };
+        let resolver = resolver(config, slow);
+        let started = Instant::now();
+        assert!(resolver.resolve("slow.example").is_empty());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(resolver.lock()["slow.example"].ips.is_empty());
+
+        // 被放弃的查询仍占用名额，达到上限后不再启动新查询
+        for i in 1..MAX_PENDING_LOOKUPS {
+            resolver.resolve(&format!("slow{}.example", i));
+        }
+        assert_eq!(resolver.pending.load(Ordering::SeqCst), MAX_PENDING_LOOKUPS);
+        let started = Instant::now();
+        assert!(resolver.resolve("one-more.example").is_empty());
+        assert!(started.elapsed() < config.timeout);
+        // 未查询的名字不进入缓存，名额恢复后可以正常解析
+        assert!(!resolver.lock().contains_key("one-more.example"));
+        assert_eq!(resolver.pending.load(Ordering::SeqCst), MAX_PENDING_LOOKUPS);
+
+        // 查询线程结束后释放名额
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while resolver.pending.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(resolver.pending.load(Ordering::SeqCst), 0);
+    }
+}
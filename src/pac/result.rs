@@ -0,0 +1,262 @@
+// src/pac/result.rs
+//! Parser for the string returned by `FindProxyForURL`.
+
+use crate::proxy::{ProxyDescriptor, ProxyServer};
+use std::fmt;
+
+/// A `FindProxyForURL` result entry that could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacDiagnostic {
+    /// Zero-based position of the entry in the `;`-separated result.
+    pub index: usize,
+    /// The entry as returned by the script, trimmed.
+    pub entry: String,
+    pub reason: String,
+}
+
+impl fmt::Display for PacDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PAC entry {} '{}': {}",
+            self.index + 1,
+            self.entry,
+            self.reason
+        )
+    }
+}
+
+/// A parsed `FindProxyForURL` result: the usable entries in order, plus a diagnostic
+/// for every malformed one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacResult {
+    pub proxies: Vec<ProxyDescriptor>,
+    pub diagnostics: Vec<PacDiagnostic>,
+}
+
+/// Parses a `FindProxyForURL` result such as `"PROXY a:8080; SOCKS5 b:1080; DIRECT"`.
+///
+/// Entries are separated by `;`; empty entries are ignored. Each entry is `DIRECT` or a
+/// type followed by `host[:port]`, with any amount of whitespace between them. Types
+/// are case-insensitive:
+///
+/// | Type              | Descriptor                   | Default port |
+/// |-------------------|------------------------------|--------------|
+/// | `PROXY`, `HTTP`   | [`ProxyDescriptor::Http`]    | 80           |
+/// | `HTTPS`           | [`ProxyDescriptor::Https`]   | 443          |
+/// | `SOCKS`, `SOCKS4` | [`ProxyDescriptor::Socks4`]  | 1080         |
+/// | `SOCKS4A`         | [`ProxyDescriptor::Socks4a`] | 1080         |
+/// | `SOCKS5`          | [`ProxyDescriptor::Socks5`]  | 1080         |
+/// | `QUIC`            | [`ProxyDescriptor::Quic`]    | 443          |
+///
+/// As in the original Netscape specification, plain `SOCKS` means SOCKS4. Anything
+/// else, including a bare `host:port` without a type, is reported in
+/// [`PacResult::diagnostics`] and left out of [`PacResult::proxies`].
+///
+/// ```
+/// use proxyparser::pac::parse_pac_result;
+///
+/// let result = parse_pac_result("proxy  a.corp:8080;\tSOCKS b.corp; c.corp:3128; DIRECT");
+/// let proxies: Vec<String> = result.proxies.iter().map(ToString::to_string).collect();
+/// assert_eq!(proxies, ["http://a.corp:8080", "socks4://b.corp:1080", "DIRECT"]);
+/// assert_eq!(result.diagnostics[0].entry, "c.corp:3128");
+/// ```
+pub fn parse_pac_result(raw: &str) -> PacResult {
+    let mut result = PacResult::default();
+    for (index, entry) in raw.split(';').enumerate() {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        match parse_entry(entry) {
+            Ok(proxy) => result.proxies.push(proxy),
+            Err(reason) => result.diagnostics.push(PacDiagnostic {
+                index,
+                entry: entry.to_string(),
+                reason,
+            }),
+        }
+    }
+    result
+}
+
+fn parse_entry(entry: &str) -> Result<ProxyDescriptor, String> {
+    let mut tokens = entry.split_whitespace();
+    let kind = tokens.next().unwrap_or_default();
+    let address = tokens.next();
+    if let Some(extra) = tokens.next() {
+        return Err(format!("unexpected '{}' after the proxy address", extra));
+    }
+
+    let (scheme, port) = match kind.to_ascii_uppercase().as_str() {
+        "DIRECT" => {
+            return match address {
+                None => Ok(ProxyDescriptor::Direct),
+                Some(address) => Err(format!("DIRECT takes no address, got '{}'", address)),
+            }
+        }
+        "PROXY" | "HTTP" => ("http", 80),
+        "HTTPS" => ("https", 443),
+        "SOCKS" | "SOCKS4" => ("socks4", 1080),
+        "SOCKS4A" => ("socks4a", 1080),
+        "SOCKS5" => ("socks5", 1080),
+        "QUIC" => ("quic", 443),
+        _ if address.is_none() && kind.contains(':') => {
+            return Err("missing proxy type (e.g. PROXY)".to_string())
+        }
+        _ => return Err(format!("unknown proxy type '{}'", kind)),
+    };
+
+    let address = address.ok_or_else(|| format!("{} needs a host[:port]", kind))?;
+    let server = parse_address(address, port)?;
+    ProxyDescriptor::from_scheme(scheme, server)
+        .ok_or_else(|| format!("unsupported proxy type '{}'", kind))
+}
+
+/// `host`, `host:port`, `[v6]` or `[v6]:port`.
+fn parse_address(address: &str, default_port: u16) -> Result<ProxyServer, String> {
+    let invalid = || format!("invalid proxy address '{}'", address);
+
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').ok_or_else(invalid)?;
+        if host.parse::<std::net::Ipv6Addr>().is_err() {
+            return Err(invalid());
+        }
+        match after {
+            "" => (host, None),
+            _ => (host, Some(after.strip_prefix(':').ok_or_else(invalid)?)),
+        }
+    } else {
+        match address.split_once(':') {
+            Some((_, port)) if port.contains(':') => {
+                return Err(format!(
+                    "IPv6 proxy address '{}' must be written in brackets",
+                    address
+                ))
+            }
+            Some((host, port)) => (host, Some(port)),
+            None => (address, None),
+        }
+    };
+
+    let valid_host = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ':'));
+    if !valid_host {
+        return Err(invalid());
+    }
+    let port = match port {
+        None => default_port,
+        Some(port) => match port.parse::<u16>() {
+            Ok(port) if port != 0 => port,
+            _ => return Err(format!("invalid port '{}'", port)),
+        },
+    };
+    Ok(ProxyServer::new(host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies(raw: &str) -> Vec<String> {
+        let result = parse_pac_result(raw);
+        assert_eq!(result.diagnostics, [], "{}", raw);
+        result.proxies.iter().map(ToString::to_string).collect()
+    }
+
+    /// The single diagnostic for `raw`, which must have no usable entries.
+    fn diagnostic(raw: &str) -> PacDiagnostic {
+        let mut result = parse_pac_result(raw);
+        assert_eq!(result.proxies, [], "{}", raw);
+        assert_eq!(result.diagnostics.len(), 1, "{}", raw);
+        result.diagnostics.remove(0)
+    }
+
+    #[test]
+    fn types_and_default_ports() {
+        assert_eq!(
+            proxies("HTTP a; PROXY b; HTTPS c; SOCKS d; SOCKS4 e; SOCKS4A f; SOCKS5 g; QUIC h"),
+            [
+                "http://a:80",
+                "http://b:80",
+                "https://c:443",
+                "socks4://d:1080",
+                "socks4://e:1080",
+                "socks4a://f:1080",
+                "socks5://g:1080",
+                "quic://h:443",
+            ]
+        );
+        assert_eq!(
+            proxies("SOCKS4A f:9050; QUIC h:8443; HTTPS c:8443"),
+            ["socks4a://f:9050", "quic://h:8443", "https://c:8443"]
+        );
+    }
+
+    #[test]
+    fn case_and_whitespace() {
+        assert_eq!(
+            proxies("proxy\ta:1;\tSocks5  b:2 ;direct;  DiReCt  "),
+            ["http://a:1", "socks5://b:2", "DIRECT", "DIRECT"]
+        );
+    }
+
+    #[test]
+    fn ipv6_addresses() {
+        assert_eq!(
+            proxies("PROXY [2001:db8::1]:8080; SOCKS5 [::1]"),
+            ["http://[2001:db8::1]:8080", "socks5://[::1]:1080"]
+        );
+        let unbracketed = diagnostic("PROXY 2001:db8::1");
+        assert_eq!(unbracketed.entry, "PROXY 2001:db8::1");
+        assert!(
+            unbracketed.reason.contains("must be written in brackets"),
+            "{}",
+            unbracketed.reason
+        );
+        assert_eq!(
+            diagnostic("PROXY [not-v6]:80").reason,
+            "invalid proxy address '[not-v6]:80'"
+        );
+        assert_eq!(
+            diagnostic("PROXY [::1]8080").reason,
+            "invalid proxy address '[::1]8080'"
+        );
+    }
+
+    #[test]
+    fn malformed_entries() {
+        assert_eq!(
+            diagnostic("DIRECT x").reason,
+            "DIRECT takes no address, got 'x'"
+        );
+        assert_eq!(diagnostic("PROXY a:0").reason, "invalid port '0'");
+        assert_eq!(diagnostic("PROXY a:65536").reason, "invalid port '65536'");
+        assert_eq!(proxies("PROXY a:65535"), ["http://a:65535"]);
+        assert_eq!(diagnostic("PROXY").reason, "PROXY needs a host[:port]");
+        assert_eq!(
+            diagnostic("PROXY a:1 b:2").reason,
+            "unexpected 'b:2' after the proxy address"
+        );
+        assert_eq!(diagnostic("FTP a:21").reason, "unknown proxy type 'FTP'");
+    }
+
+    #[test]
+    fn diagnostics_keep_the_entry_index() {
+        let missing_type = diagnostic(";;a:1");
+        assert_eq!(missing_type.index, 2);
+        assert_eq!(missing_type.entry, "a:1");
+        assert_eq!(missing_type.reason, "missing proxy type (e.g. PROXY)");
+        assert_eq!(
+            missing_type.to_string(),
+            "PAC entry 3 'a:1': missing proxy type (e.g. PROXY)"
+        );
+
+        let result = parse_pac_result("PROXY a:1; bogus; ; DIRECT");
+        assert_eq!(result.proxies.len(), 2);
+        assert_eq!(result.diagnostics[0].index, 1);
+        assert_eq!(parse_pac_result("  ; ;"), PacResult::default());
+    }
+}
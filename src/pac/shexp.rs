@@ -0,0 +1,157 @@
+// src/pac/shexp.rs
+//! Shell-expression patterns used by `shExpMatch`.
+//!
+//! `*` matches any run of characters, `?` a single character and `[...]` a character
+//! class (`[!...]` or `[^...]` negated, `a-z` ranges), as in browsers. Every other
+//! character matches itself; `\` makes the next character literal.
+
+use regex::Regex;
+
+/// Translates a shell expression into an anchored regular expression.
+pub(crate) fn shexp_to_regex(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::with_capacity(pattern.len() * 2 + 8);
+    out.push_str("(?s)^");
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                out.push_str(&regex::escape(&chars[i].to_string()));
+            }
+            '[' => match class_end(&chars, i) {
+                Some(end) => {
+                    push_class(&mut out, &chars[i + 1..end]);
+                    i = end;
+                }
+                // 没有闭合的 '[' 按字面量处理
+                None => out.push_str(r"\["),
+            },
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    out.push('$');
+    out
+}
+
+/// Index of the `]` closing the class opened at `start`. A `]` right after `[`, `[!`
+/// or `[^` belongs to the class.
+fn class_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if matches!(chars.get(i), Some('!') | Some('^')) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    (i..chars.len()).find(|&j| chars[j] == ']')
+}
+
+fn push_class(out: &mut String, body: &[char]) {
+    out.push('[');
+    let body = match body.first() {
+        Some('!') | Some('^') => {
+            out.push('^');
+            &body[1..]
+        }
+        _ => body,
+    };
+    for (i, c) in body.iter().enumerate() {
+        // 只有夹在两个字符之间的 '-' 表示范围，其余一律转义
+        if *c == '-' && i > 0 && i + 1 < body.len() {
+            out.push('-');
+        } else {
+            out.push_str(&regex::escape(&c.to_string()));
+        }
+    }
+    out.push(']');
+}
+
+/// Compiles a shell expression; `None` when it is not a valid pattern (e.g. `[z-a]`).
+pub(crate) fn compile_shexp(pattern: &str) -> Option<Regex> {
+    Regex::new(&shexp_to_regex(pattern)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        compile_shexp(pattern)
+            .unwrap_or_else(|| panic!("{:?} should compile", pattern))
+            .is_match(text)
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("*.example.com", "www.example.com"));
+        assert!(!matches("*.example.com", "example.com"));
+        assert!(matches("http://*/*", "http://host/path"));
+        assert!(matches("??.corp", "ab.corp"));
+        assert!(!matches("??.corp", "abc.corp"));
+        // '.' 和 '+' 等正则元字符按字面量匹配
+        assert!(!matches("a.b+c", "axbbc"));
+        assert!(matches("a.b+c", "a.b+c"));
+        // '*' 跨越换行
+        assert!(matches("a*b", "a\nb"));
+        assert_eq!(shexp_to_regex("*"), "(?s)^.*$");
+    }
+
+    #[test]
+    fn negated_classes() {
+        assert_eq!(shexp_to_regex("[!a-c]"), "(?s)^[^a-c]$");
+        assert_eq!(shexp_to_regex("[^a-c]"), "(?s)^[^a-c]$");
+        assert!(matches("[!a-c]x", "dx"));
+        assert!(!matches("[!a-c]x", "bx"));
+        assert!(matches("host[^0-9]", "hostA"));
+        assert!(!matches("host[^0-9]", "host7"));
+    }
+
+    #[test]
+    fn bracket_as_first_member() {
+        assert_eq!(shexp_to_regex("[]a]"), r"(?s)^[\]a]$");
+        assert!(matches("[]a]", "]"));
+        assert!(matches("[]a]", "a"));
+        assert!(!matches("[]a]", "b"));
+        assert!(matches("[!]a]", "b"));
+        assert!(!matches("[!]a]", "]"));
+        // 类中的 '-' 在首尾时是字面量
+        assert!(matches("[-a]", "-"));
+        assert!(matches("[a-]", "-"));
+        assert!(!matches("[a-c]", "-"));
+    }
+
+    #[test]
+    fn unclosed_bracket_is_literal() {
+        assert_eq!(shexp_to_regex("a[b"), r"(?s)^a\[b$");
+        assert!(matches("a[b", "a[b"));
+        assert!(!matches("a[b", "ab"));
+        assert!(matches("[]", "[]"));
+        assert!(matches("[!]", "[!]"));
+        assert!(matches("*[", "x["));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(matches(r"a\*b", "a*b"));
+        assert!(!matches(r"a\*b", "axxb"));
+        assert!(matches(r"a\?b", "a?b"));
+        assert!(!matches(r"a\?b", "axb"));
+        assert!(matches(r"\[x]", "[x]"));
+        assert!(!matches(r"\[x]", "x"));
+        // 末尾的 '\' 匹配自身
+        assert!(matches(r"dir\", r"dir\"));
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        assert!(compile_shexp("[z-a]").is_none());
+        assert!(compile_shexp("host[9-0].corp").is_none());
+        assert!(compile_shexp("[a-a]").is_some());
+    }
+}
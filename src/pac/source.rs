@@ -0,0 +1,169 @@
+// src/pac/source.rs
+use percent_encoding::percent_decode_str;
+use std::path::PathBuf;
+use url::Url;
+
+/// Where a PAC script comes from.
+///
+/// Every source is loaded through [`PacCache::load`](super::PacCache::load) and
+/// evaluated by the same [`PacEngine`](super::PacEngine).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacSource {
+    /// An `http://` or `https://` URL, downloaded and cached per HTTP caching rules.
+    Url(String),
+    /// A local file, from a `file://` URL or a plain path. Read on every load so edits
+    /// are picked up immediately.
+    File(PathBuf),
+    /// A `data:` URL (RFC 2397) carrying the script, percent-encoded or base64.
+    Data(String),
+    /// The script text itself.
+    Script(String),
+}
+
+impl PacSource {
+    /// Classifies a PAC location as configured by a user or the system: an `http(s)://`,
+    /// `file://` or `data:` URL, or else a filesystem path.
+    ///
+    /// ```
+    /// use proxyparser::pac::PacSource;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// assert_eq!(PacSource::from_location("file:///etc/proxy.pac")?, PacSource::File(PathBuf::from("/etc/proxy.pac")));
+    /// assert_eq!(PacSource::from_location("./dev.pac")?, PacSource::File(PathBuf::from("./dev.pac")));
+    /// assert!(matches!(PacSource::from_location("HTTP://wpad/wpad.dat")?, PacSource::Url(_)));
+    /// assert!(PacSource::from_location("ftp://corp/proxy.pac").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_location(location: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let location = location.trim();
+        let lower = location.to_ascii_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            return Ok(PacSource::Url(location.to_string()));
+        }
+        if lower.starts_with("data:") {
+            return Ok(PacSource::Data(location.to_string()));
+        }
+        if lower.starts_with("file:") {
+            let url = Url::parse(location)?;
+            let path = url
+                .to_file_path()
+                .map_err(|_| format!("not a local file URL: {}", location))?;
+            return Ok(PacSource::File(path));
+        }
+        if let Some((scheme, _)) = location.split_once("://") {
+            return Err(format!("unsupported PAC URL scheme '{}': {}", scheme, location).into());
+        }
+        if location.is_empty() {
+            return Err("empty PAC location".into());
+        }
+        Ok(PacSource::File(PathBuf::from(location)))
+    }
+
+    /// Cache key and name used in log messages.
+    pub(crate) fn key(&self) -> String {
+        match self {
+            PacSource::Url(url) => url.clone(),
+            PacSource::File(path) => format!("file://{}", path.display()),
+            PacSource::Data(_) => "data: URL".to_string(),
+            PacSource::Script(_) => "inline script".to_string(),
+        }
+    }
+}
+
+/// Decodes a `data:` URL into its payload and media type (e.g.
+/// `application/x-ns-proxy-autoconfig;charset=utf-8`, without `;base64`).
+pub(crate) fn decode_data_url(
+    data_url: &str,
+) -> Result<(Vec<u8>, String), Box<dyn std::error::Error>> {
+    let body = match data_url.get(.."data:".len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("data:") => &data_url["data:".len()..],
+        _ => return Err(format!("not a data: URL: {}", data_url).into()),
+    };
+    let (header, payload) = body
+        .split_once(',')
+        .ok_or("malformed data: URL (missing ',')")?;
+    let bytes: Vec<u8> = percent_decode_str(payload).collect();
+    match header.rsplit_once(';') {
+        Some((media_type, param)) if param.trim().eq_ignore_ascii_case("base64") => {
+            let bytes = decode_base64(&bytes).ok_or("invalid base64 in data: URL")?;
+            Ok((bytes, media_type.to_string()))
+        }
+        _ if header.trim().eq_ignore_ascii_case("base64") => {
+            let bytes = decode_base64(&bytes).ok_or("invalid base64 in data: URL")?;
+            Ok((bytes, String::new()))
+        }
+        _ => Ok((bytes, header.to_string())),
+    }
+}
+
+/// Forgiving base64 as in the WHATWG "forgiving-base64 decode": whitespace is ignored
+/// and padding is optional.
+fn decode_base64(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    let mut data = input
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect::<Vec<u8>>();
+    while data.last() == Some(&b'=') {
+        data.pop();
+    }
+    for b in data {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_urls() {
+        let (bytes, media_type) =
+            decode_data_url("data:application/x-ns-proxy-autoconfig,function%20f()%7B%7D").unwrap();
+        assert_eq!(bytes, b"function f(){}");
+        assert_eq!(media_type, "application/x-ns-proxy-autoconfig");
+
+        let (bytes, media_type) =
+            decode_data_url("DATA:text/plain;charset=utf-8;base64,aGk=").unwrap();
+        assert_eq!(bytes, b"hi");
+        assert_eq!(media_type, "text/plain;charset=utf-8");
+
+        assert!(decode_data_url("data:text/plain").is_err());
+        assert!(decode_data_url("data:;base64,@@@").is_err());
+    }
+
+    #[test]
+    fn not_a_data_url() {
+        // 公开的 PacSource::Data 可以装任何字符串，不能假定前缀存在
+        for input in [
+            "",
+            "x",
+            "data",
+            "http://wpad/wpad.dat",
+            "dätä:x,y",
+            "日本語,x",
+        ] {
+            let err = decode_data_url(input).unwrap_err();
+            assert!(err.to_string().starts_with("not a data: URL"), "{}", input);
+        }
+    }
+}
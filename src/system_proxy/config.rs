@@ -0,0 +1,119 @@
+// src/system_proxy/config.rs
+use crate::hostname::BypassList;
+use crate::proxy::ProxyDescriptor;
+use std::fmt;
+use url::Url;
+
+/// The proxy settings of the operating system or desktop, as configured and before any
+/// PAC script is downloaded or run.
+///
+/// Returned by [`get_system_proxy_config`](super::get_system_proxy_config), mainly so that
+/// tools can show what the system is set to.
+// 每次查询只构造一次，不值得为 Manual 装箱
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SystemProxyConfig {
+    /// No proxy.
+    #[default]
+    Direct,
+    /// Fixed proxies per URL scheme, with hosts that bypass them.
+    Manual(ManualProxyConfig),
+    /// A PAC script at this location: an `http(s)://`, `file://` or `data:` URL, or a
+    /// path (see [`PacSource::from_location`](crate::pac::PacSource::from_location)).
+    AutoConfigUrl(String),
+    /// Web Proxy Auto-Discovery: the PAC URL is looked up on the network (DHCP, DNS).
+    ///
+    /// Windows may also have a PAC URL or manual proxy configured next to
+    /// auto-detection. As in WinHTTP, auto-detection is tried first and those settings
+    /// are only used when it finds no PAC URL; elsewhere nothing is used then.
+    AutoDetect,
+}
+
+/// Manually configured proxies. A URL whose scheme has no proxy of its own goes through
+/// the SOCKS proxy, if any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManualProxyConfig {
+    pub http: Option<ProxyDescriptor>,
+    pub https: Option<ProxyDescriptor>,
+    /// The proxy for `ftp://` URLs, always given as an `http://` descriptor because
+    /// [`ProxyDescriptor`] has no FTP scheme. On Windows this is an HTTP proxy that fetches
+    /// FTP URLs, but macOS and GNOME may name a native FTP proxy (an FTP gateway) that
+    /// does not speak HTTP; the settings do not say which.
+    pub ftp: Option<ProxyDescriptor>,
+    pub socks: Option<ProxyDescriptor>,
+    /// Hosts reached without proxy, as entries of a [`BypassList`].
+    pub bypass: Vec<String>,
+}
+
+impl ManualProxyConfig {
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none() && self.ftp.is_none() && self.socks.is_none()
+    }
+
+    /// The proxy for `url`: `None` when the host is in the bypass list or no proxy is
+    /// configured for the scheme.
+    ///
+    /// ```
+    /// use proxyparser::system_proxy::ManualProxyConfig;
+    /// use url::Url;
+    ///
+    /// let config = ManualProxyConfig {
+    ///     http: Some("http://proxy.corp:3128".parse().unwrap()),
+    ///     socks: Some("socks5://socks.corp:1080".parse().unwrap()),
+    ///     bypass: vec!["localhost".into(), ".corp".into()],
+    ///     ..ManualProxyConfig::default()
+    /// };
+    /// let proxy = |url: &str| config.proxy_for(&Url::parse(url).unwrap()).map(|p| p.to_string());
+    /// assert_eq!(proxy("http://example.com/").as_deref(), Some("http://proxy.corp:3128"));
+    /// assert_eq!(proxy("https://example.com/").as_deref(), Some("socks5://socks.corp:1080"));
+    /// assert_eq!(proxy("http://wiki.corp/"), None);
+    /// ```
+    pub fn proxy_for(&self, url: &Url) -> Option<&ProxyDescriptor> {
+        if let Some(host) = url.host_str() {
+            if BypassList::parse(&self.bypass.join(",")).matches(host) {
+                return None;
+            }
+        }
+        let by_scheme = match url.scheme() {
+            "http" | "ws" => self.http.as_ref(),
+            "https" | "wss" => self.https.as_ref(),
+            "ftp" => self.ftp.as_ref(),
+            _ => None,
+        };
+        by_scheme.or(self.socks.as_ref())
+    }
+}
+
+impl fmt::Display for SystemProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SystemProxyConfig::Direct => write!(f, "direct"),
+            SystemProxyConfig::Manual(manual) => write!(f, "manual: {}", manual),
+            SystemProxyConfig::AutoConfigUrl(location) => write!(f, "PAC {}", location),
+            SystemProxyConfig::AutoDetect => write!(f, "auto-detect (WPAD)"),
+        }
+    }
+}
+
+impl fmt::Display for ManualProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let proxies = [
+            ("http", &self.http),
+            ("https", &self.https),
+            ("ftp", &self.ftp),
+            ("socks", &self.socks),
+        ];
+        let mut parts: Vec<String> = proxies
+            .iter()
+            .filter_map(|(scheme, proxy)| Some(format!("{}={}", scheme, proxy.as_ref()?)))
+            .collect();
+        if parts.is_empty() {
+            parts.push("no proxies".to_string());
+        }
+        write!(f, "{}", parts.join(", "))?;
+        if !self.bypass.is_empty() {
+            write!(f, "; bypass {}", self.bypass.join(","))?;
+        }
+        Ok(())
+    }
+}
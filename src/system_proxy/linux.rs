@@ -1,164 +1,144 @@
 // src/system_proxy/linux.rs
 
+#[cfg(target_os = "linux")]
+use super::{ManualProxyConfig, SystemProxyConfig};
+#[cfg(target_os = "linux")]
+use crate::proxy::ProxyDescriptor;
 #[cfg(target_os = "linux")]
 use crate::{log_info, log_warn};
 use std::process::Command;
-use url::Url;
+
 #[cfg(target_os = "linux")]
-pub fn get_linux_proxy(url: &str) -> Option<String> {
-    let parsed_url = match Url::parse(url) {
-        Ok(u) => u,
-        Err(_) => return None,
-    };
-    let scheme = parsed_url.scheme();
+mod dconf;
+#[cfg(target_os = "linux")]
+mod dhcp;
+#[cfg(target_os = "linux")]
+mod gnome;
+#[cfg(target_os = "linux")]
+mod gvariant;
+#[cfg(target_os = "linux")]
+mod gvdb;
+#[cfg(target_os = "linux")]
+mod wpad;
 
+#[cfg(target_os = "linux")]
+pub fn get_linux_proxy_config() -> SystemProxyConfig {
     // 1. GNOME (most common on Ubuntu/Fedora etc.)
-    if let Some(proxy) = get_gnome_proxy(&parsed_url) {
-        return Some(proxy);
+    let gnome = get_gnome_proxy_config();
+    if let Some(config) = gnome.as_ref().filter(|c| **c != SystemProxyConfig::Direct) {
+        return config.clone();
     }
 
     // 2. KDE
-    if let Some(proxy) = get_kde_proxy(&parsed_url, scheme) {
-        return Some(proxy);
+    if let Some(config) = get_kde_proxy_config() {
+        return config;
     }
 
-    // 3. Fallback: environment variables (some apps set http_proxy etc.)
-    if let Some(env_proxy) = std::env::var("http_proxy")
-        .ok()
-        .or_else(|| std::env::var("HTTP_PROXY").ok())
-    {
-        if !env_proxy.is_empty() {
-            return Some(env_proxy);
-        }
-    }
-    if let Some(env_proxy) = std::env::var("https_proxy")
-        .ok()
-        .or_else(|| std::env::var("HTTPS_PROXY").ok())
-    {
-        if !env_proxy.is_empty() {
-            return Some(env_proxy);
-        }
-    }
+    gnome.unwrap_or_default()
+}
 
-    None
+// WPAD：与 Windows 相同，先 DHCP（option 252）后 DNS
+#[cfg(target_os = "linux")]
+pub(crate) fn discover_wpad_url() -> Option<String> {
+    dhcp::discover_wpad_url().or_else(wpad::discover_wpad_url)
 }
 
 // GNOME proxy (gsettings)
 #[cfg(target_os = "linux")]
-fn get_gnome_proxy(url: &Url) -> Option<String> {
-    let mode_output = Command::new("gsettings")
-        .args(["get", "org.gnome.system.proxy", "mode"])
-        .output()
-        .ok()?;
-    let mode = String::from_utf8_lossy(&mode_output.stdout)
-        .trim()
-        .trim_matches('\'')
-        .to_string();
-
-    if mode == "manual" {
-        let scheme_key = match url.scheme() {
-            "http" => "http",
-            "https" => "https",
-            _ => return None,
-        };
-
-        let host_output = Command::new("gsettings")
-            .args([
-                "get",
-                "org.gnome.system.proxy.http",
-                &format!("{}-host", scheme_key),
-            ])
-            .output()
-            .ok()?;
-        let host = String::from_utf8_lossy(&host_output.stdout)
-            .trim()
-            .trim_matches('\'')
-            .to_string();
-
-        let port_output = Command::new("gsettings")
-            .args([
-                "get",
-                "org.gnome.system.proxy.http",
-                &format!("{}-port", scheme_key),
-            ])
-            .output()
-            .ok()?;
-        let port: i32 = String::from_utf8_lossy(&port_output.stdout)
-            .trim()
-            .parse()
-            .unwrap_or(0);
-
-        if !host.is_empty() && port > 0 {
-            return Some(format!("http://{}:{}", host, port));
-        }
-    } else if mode == "auto" {
-        let pac_output = Command::new("gsettings")
-            .args(["get", "org.gnome.system.proxy", "autoconfig-url"])
-            .output()
-            .ok()?;
-        let pac_url = String::from_utf8_lossy(&pac_output.stdout)
-            .trim()
-            .trim_matches('\'')
-            .to_string();
-        if !pac_url.is_empty() {
-            log_info!("Found GNOME PAC URL: {}", pac_url);
-            return Some(pac_url);
-        }
-    }
-
-    None
+fn get_gnome_proxy_config() -> Option<SystemProxyConfig> {
+    // 优先直接读取 dconf 数据库：无需 D-Bus 会话，也不用启动 gsettings
+    let settings = dconf::DconfProfile::from_environment()
+        .proxy_settings()
+        .or_else(gnome::read_gsettings)?;
+    Some(gnome::proxy_config(&settings))
 }
 
 // KDE proxy (kreadconfig5 or kreadconfig6)
 #[cfg(target_os = "linux")]
-fn get_kde_proxy(url: &Url, scheme: &str) -> Option<String> {
+fn get_kde_proxy_config() -> Option<SystemProxyConfig> {
     let kread = if Command::new("kreadconfig6").output().is_ok() {
         "kreadconfig6"
     } else {
         "kreadconfig5"
     };
-
-    let proxy_type_output = Command::new(kread)
-        .args(["--group", "Proxy Settings", "--key", "ProxyType"])
-        .output()
-        .ok()?;
-    let proxy_type = String::from_utf8_lossy(&proxy_type_output.stdout)
-        .trim()
-        .to_string();
-
-    if proxy_type == "1" {
-        // Manual
-        let proxy_key = match scheme {
-            "http" => "httpProxy",
-            "https" => "httpsProxy",
-            _ => return None,
-        };
-
-        let proxy_output = Command::new(kread)
-            .args(["--group", "Proxy Settings", "--key", proxy_key])
+    let read = |key: &str| -> Option<String> {
+        let output = Command::new(kread)
+            .args([
+                "--file",
+                "kioslaverc",
+                "--group",
+                "Proxy Settings",
+                "--key",
+                key,
+            ])
             .output()
             .ok()?;
-        let proxy = String::from_utf8_lossy(&proxy_output.stdout)
-            .trim()
-            .to_string();
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
 
-        if !proxy.is_empty() {
-            return Some(format!("http://{}", proxy));
+    match read("ProxyType")?.as_str() {
+        "1" => {
+            // Manual
+            let server = |key: &str| parse_kde_proxy(&read(key).unwrap_or_default());
+            if read("ReversedException").as_deref() == Some("true") {
+                log_warn!(
+                    "KDE proxy exceptions are reversed (proxy only listed hosts); not supported"
+                );
+            }
+            Some(SystemProxyConfig::Manual(ManualProxyConfig {
+                http: server("httpProxy"),
+                https: server("httpsProxy"),
+                ftp: server("ftpProxy"),
+                socks: server("socksProxy"),
+                bypass: read("NoProxyFor")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(String::from)
+                    .collect(),
+            }))
         }
-    } else if proxy_type == "2" {
-        // PAC
-        let pac_output = Command::new(kread)
-            .args(["--group", "Proxy Settings", "--key", "ProxyConfigScript"])
-            .output()
-            .ok()?;
-        let pac_url = String::from_utf8_lossy(&pac_output.stdout)
-            .trim()
-            .to_string();
-        if !pac_url.is_empty() {
+        "2" => {
+            // PAC
+            let pac_url = read("Proxy Config Script")
+                .filter(|url| !url.is_empty())
+                .or_else(|| read("ProxyConfigScript"))
+                .unwrap_or_default();
+            if pac_url.is_empty() {
+                return None;
+            }
             log_info!("Found KDE PAC URL: {}", pac_url);
-            return Some(pac_url);
+            Some(SystemProxyConfig::AutoConfigUrl(pac_url))
         }
+        // 自动检测（WPAD）
+        "3" => Some(SystemProxyConfig::AutoDetect),
+        _ => None,
+    }
+}
+
+/// KDE writes `http://proxy.corp 3128` (host and port separated by a space) or
+/// `proxy.corp:3128`.
+#[cfg(target_os = "linux")]
+fn parse_kde_proxy(value: &str) -> Option<ProxyDescriptor> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
     }
+    let value = match value.rsplit_once(' ') {
+        Some((host, port)) => format!("{}:{}", host.trim(), port.trim()),
+        None => value.to_string(),
+    };
+    parse_proxy(&value)
+}
 
-    None
+#[cfg(target_os = "linux")]
+fn parse_proxy(value: &str) -> Option<ProxyDescriptor> {
+    match value.parse() {
+        Ok(proxy) => Some(proxy),
+        Err(e) => {
+            log_warn!("Ignoring system proxy: {}", e);
+            None
+        }
+    }
 }
@@ -0,0 +1,278 @@
+// src/system_proxy/linux/dconf.rs
+//! GNOME proxy settings read straight from the dconf databases, without a D-Bus session
+//! or the `gsettings` tool (SSH sessions, services, containers).
+
+use super::gnome::{Settings, SCHEMA};
+use super::gvariant::{self, GVariant};
+use super::gvdb::{Entry, Table};
+use crate::log_debug;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// dconf path of the `org.gnome.system.proxy` schema.
+const PROXY_PATH: &str = "/system/proxy/";
+const PROFILE_DIR: &str = "/etc/dconf/profile";
+const SYSTEM_DB_DIR: &str = "/etc/dconf/db";
+
+/// The databases of a dconf profile, highest priority first: normally the user database
+/// followed by system-wide databases set up by the administrator.
+///
+/// A key locked in a system database cannot be overridden by the databases listed
+/// before it, as in dconf itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DconfProfile {
+    databases: Vec<PathBuf>,
+}
+
+impl DconfProfile {
+    pub(crate) fn new(databases: Vec<PathBuf>) -> Self {
+        DconfProfile { databases }
+    }
+
+    /// The profile dconf would use: the one named by `$DCONF_PROFILE`, else
+    /// `/etc/dconf/profile/user`, else the user database alone.
+    pub(crate) fn from_environment() -> Self {
+        let profile_path = match env::var("DCONF_PROFILE") {
+            Ok(name) if name.starts_with('/') => PathBuf::from(name),
+            Ok(name) if !name.is_empty() => Path::new(PROFILE_DIR).join(name),
+            _ => Path::new(PROFILE_DIR).join("user"),
+        };
+        match fs::read_to_string(profile_path) {
+            Ok(profile) => DconfProfile::parse(&profile),
+            Err(_) => DconfProfile::new(vec![user_db_path("user")]),
+        }
+    }
+
+    /// Parses a profile file: one `user-db:NAME`, `system-db:NAME` or `file-db:PATH`
+    /// per line, `#` comments. `service-db` lines have no file to read and are skipped.
+    pub(crate) fn parse(profile: &str) -> Self {
+        let databases = profile
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter_map(|line| {
+                let (kind, name) = line.split_once(':')?;
+                match kind.trim() {
+                    "user-db" => Some(user_db_path(name.trim())),
+                    "system-db" => Some(Path::new(SYSTEM_DB_DIR).join(name.trim())),
+                    "file-db" => Some(PathBuf::from(name.trim())),
+                    _ => None,
+                }
+            })
+            .collect();
+        DconfProfile { databases }
+    }
+
+    /// The proxy keys stored in the profile's databases. `None` when none of the
+    /// databases could be read.
+    pub(crate) fn proxy_settings(&self) -> Option<Settings> {
+        let databases: Vec<Option<Database>> = self
+            .databases
+            .iter()
+            .map(|path| Database::read(path))
+            .collect();
+        if databases.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let keys: HashSet<&String> = databases
+            .iter()
+            .flatten()
+            .flat_map(|db| db.values.keys())
+            .collect();
+        let mut settings = Settings::new();
+        for key in keys {
+            // 最底层加锁的数据库决定从哪一层开始读取（与 dconf 引擎一致）
+            let lock_level = (1..databases.len())
+                .rev()
+                .find(|&i| {
+                    databases[i]
+                        .as_ref()
+                        .is_some_and(|db| db.locks.contains(key))
+                })
+                .unwrap_or(0);
+            let value = databases[lock_level..]
+                .iter()
+                .flatten()
+                .find_map(|db| db.values.get(key));
+            if let (Some(value), Some(schema_key)) = (value, schema_key(key)) {
+                settings.insert(schema_key, value.clone());
+            }
+        }
+        Some(settings)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/dconf/NAME`, by default `~/.config/dconf/NAME`.
+fn user_db_path(name: &str) -> PathBuf {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .unwrap_or_default();
+    config_home.join("dconf").join(name)
+}
+
+/// `/system/proxy/http/host` -> (`org.gnome.system.proxy.http`, `host`).
+fn schema_key(path: &str) -> Option<(String, String)> {
+    let rest = path.strip_prefix(PROXY_PATH)?;
+    match rest.split_once('/') {
+        Some((child, key)) => Some((format!("{}.{}", SCHEMA, child), key.to_string())),
+        None => Some((SCHEMA.to_string(), rest.to_string())),
+    }
+}
+
+/// The proxy keys and locks of one database file.
+struct Database {
+    values: HashMap<String, GVariant>,
+    locks: HashSet<String>,
+}
+
+impl Database {
+    fn read(path: &Path) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        let Some(root) = Table::root(&data) else {
+            log_debug!("dconf: {} is not a GVDB database", path.display());
+            return None;
+        };
+
+        let mut database = Database {
+            values: HashMap::new(),
+            locks: HashSet::new(),
+        };
+        for (key, entry) in root.entries() {
+            match entry {
+                Entry::Value(bytes) if key.starts_with(PROXY_PATH) => {
+                    match gvariant::from_variant_bytes(bytes) {
+                        Some(value) => {
+                            database.values.insert(key, value);
+                        }
+                        None => log_debug!("dconf: cannot decode {} in {}", key, path.display()),
+                    }
+                }
+                Entry::Table(locks) if key == ".locks" => {
+                    database.locks = locks.entries().into_iter().map(|(key, _)| key).collect();
+                }
+                _ => {}
+            }
+        }
+        Some(database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! `fixtures/dconf/user` was written by dconf-service through `gsettings set`. `site`
+    //! is a system database that sets mode `auto` with a PAC URL and locks both keys;
+    //! `site-defaults` holds the same values without locks.
+
+    use super::*;
+    use crate::proxy::{ProxyDescriptor, ProxyServer};
+    use crate::system_proxy::linux::gnome;
+    use crate::system_proxy::{ManualProxyConfig, SystemProxyConfig};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/dconf")
+            .join(name)
+    }
+
+    /// The GNOME proxy configuration stored in `databases`, with schema defaults for
+    /// unset keys.
+    fn config(databases: &[PathBuf]) -> Option<SystemProxyConfig> {
+        Some(gnome::proxy_config(
+            &DconfProfile::new(databases.to_vec()).proxy_settings()?,
+        ))
+    }
+
+    fn http(host: &str, port: u16) -> Option<ProxyDescriptor> {
+        Some(ProxyDescriptor::Http(ProxyServer::new(host, port)))
+    }
+
+    #[test]
+    fn user_database() {
+        let config = config(&[fixture("user")]);
+        let expected = ManualProxyConfig {
+            http: Some(ProxyDescriptor::Http(
+                ProxyServer::new("proxy.corp.example", 3128)
+                    .with_credentials("alice", Some("s3cr\\\"et".to_string())),
+            )),
+            https: http("secure.corp.example", 8443),
+            // use-same-proxy 为 false：FTP 不回退到 HTTP 代理
+            ftp: None,
+            socks: Some(ProxyDescriptor::Socks5(ProxyServer::new(
+                "socks.corp.example",
+                1080,
+            ))),
+            bypass: [
+                "localhost",
+                "127.0.0.0/8",
+                "::1",
+                "*.corp.example",
+                "10.0.0.0/8",
+                "it's.odd",
+            ]
+            .map(String::from)
+            .to_vec(),
+        };
+        assert_eq!(config, Some(SystemProxyConfig::Manual(expected)));
+    }
+
+    #[test]
+    fn system_locks_override_user_values() {
+        assert_eq!(
+            config(&[fixture("user"), fixture("site")]),
+            Some(SystemProxyConfig::AutoConfigUrl(
+                "http://wpad.corp.example/proxy.pac".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn system_database_alone() {
+        assert_eq!(
+            config(&[fixture("missing-user"), fixture("site")]),
+            Some(SystemProxyConfig::AutoConfigUrl(
+                "http://wpad.corp.example/proxy.pac".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unlocked_system_values_are_defaults() {
+        // site-defaults 与 site 内容相同但没有锁：用户设置优先，未设置的键才取系统值
+        assert_eq!(
+            config(&[fixture("user"), fixture("site-defaults")]),
+            config(&[fixture("user")])
+        );
+        assert_eq!(
+            config(&[fixture("missing-user"), fixture("site-defaults")]),
+            Some(SystemProxyConfig::AutoConfigUrl(
+                "http://wpad.corp.example/proxy.pac".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn profile_file() {
+        let profile = DconfProfile::parse(&format!(
+            "# proxy policy\nservice-db:keyfile/user\nfile-db:{}\nfile-db:{}  # site policy\n",
+            fixture("user").display(),
+            fixture("site").display()
+        ));
+        assert_eq!(profile.databases, [fixture("user"), fixture("site")]);
+        assert!(matches!(
+            config(&profile.databases),
+            Some(SystemProxyConfig::AutoConfigUrl(_))
+        ));
+    }
+
+    #[test]
+    fn unreadable_databases() {
+        assert_eq!(config(&[fixture("missing")]), None);
+        // 不是 GVDB 文件
+        let not_gvdb = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        assert_eq!(config(&[not_gvdb]), None);
+    }
+}
@@ -0,0 +1,359 @@
+// src/system_proxy/linux/dhcp.rs
+//! WPAD through DHCP option 252 ("auto-proxy-config"), read from the lease files the
+//! local DHCP clients leave behind. Windows asks the DHCP server directly; on Linux the
+//! option is only visible if the client recorded it, which dhclient, NetworkManager,
+//! systemd-networkd and dhcpcd all do.
+
+use crate::{log_debug, log_info};
+use chrono::{NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// DHCP option code for the WPAD URL (private use range, de facto standard).
+const OPTION_WPAD: u8 = 252;
+
+/// Directories where the supported clients keep their IPv4 leases.
+const LEASE_DIRS: [&str; 8] = [
+    // dhclient（Debian/Ubuntu，RHEL/Fedora）
+    "/var/lib/dhcp",
+    "/var/lib/dhclient",
+    // NetworkManager：内部客户端与 dhclient 插件
+    "/var/lib/NetworkManager",
+    // systemd-networkd，文件名为接口索引
+    "/run/systemd/netif/leases",
+    // dhcpcd 7+ 保存原始 DHCP 报文
+    "/var/lib/dhcpcd",
+    "/var/lib/dhcpcd5",
+    "/var/db/dhcpcd",
+    "/run/dhcpcd",
+];
+
+/// Looks for a WPAD URL in the lease files under [`LEASE_DIRS`].
+pub(crate) fn discover_wpad_url() -> Option<String> {
+    wpad_url_from_leases(&LEASE_DIRS)
+}
+
+/// Looks for a WPAD URL in the lease files found in `dirs`. When several leases carry
+/// one, the most recently written lease wins.
+fn wpad_url_from_leases<P: AsRef<Path>>(dirs: &[P]) -> Option<String> {
+    let mut leases: Vec<(SystemTime, PathBuf)> = dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            (metadata.is_file() && is_lease_file_name(name)).then(|| {
+                (
+                    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    entry.path(),
+                )
+            })
+        })
+        .collect();
+    leases.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (_, path) in leases {
+        let Ok(contents) = fs::read(&path) else {
+            continue;
+        };
+        match wpad_url_from_lease(&contents) {
+            Some(pac_url) => {
+                log_info!("DHCP option 252 in {}: {}", path.display(), pac_url);
+                return Some(pac_url);
+            }
+            None => log_debug!("DHCP: no WPAD option in {}", path.display()),
+        }
+    }
+    None
+}
+
+/// IPv4 lease files: `*.lease` / `*.leases` (dhclient, NetworkManager, dhcpcd) and the
+/// interface-index names used by systemd-networkd. DHCPv6 leases cannot carry option 252.
+fn is_lease_file_name(name: &str) -> bool {
+    name.ends_with(".lease")
+        || name.ends_with(".leases")
+        || (!name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Extracts the WPAD URL from the contents of a single lease file, whatever client
+/// wrote it: a raw DHCP packet (dhcpcd), a dhclient lease database, or the `KEY=VALUE`
+/// format of systemd-networkd and NetworkManager's internal client.
+fn wpad_url_from_lease(contents: &[u8]) -> Option<String> {
+    let raw = if let Some(options) = dhcp_packet_options(contents) {
+        find_option(&options, OPTION_WPAD)?
+    } else {
+        let text = String::from_utf8_lossy(contents);
+        if text.contains("lease {") {
+            from_dhclient(&text)?
+        } else {
+            from_key_value(&text)?
+        }
+    };
+    clean_url(&raw)
+}
+
+/// Windows DHCP servers usually count the terminating NUL in the option length.
+fn clean_url(raw: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(raw);
+    let url = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!url.is_empty()).then(|| url.to_string())
+}
+
+// ---- dhclient ----
+
+/// Names dhclient gives option 252: `wpad`/`proxy-auto-config` when declared in
+/// dhclient.conf (`option wpad code 252 = text;`), `unknown-252` otherwise.
+const DHCLIENT_NAMES: [&str; 4] = ["wpad", "wpad-url", "proxy-auto-config", "unknown-252"];
+
+/// The file is appended to on every renewal, so only the last unexpired lease counts.
+fn from_dhclient(text: &str) -> Option<Vec<u8>> {
+    let now = Utc::now().naive_utc();
+    let mut current: Option<(Option<Vec<u8>>, bool)> = None;
+    let mut latest = None;
+    for line in text.lines() {
+        let line = line.trim().trim_end_matches(';').trim();
+        if line.starts_with("lease ") || line == "lease{" {
+            current = Some((None, false));
+        } else if line == "}" {
+            if let Some((value, expired)) = current.take() {
+                if !expired {
+                    latest = Some(value);
+                }
+            }
+        } else if let Some((value, expired)) = current.as_mut() {
+            if let Some(rest) = line.strip_prefix("option ") {
+                let (name, data) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                if DHCLIENT_NAMES.contains(&name) {
+                    *value = parse_dhclient_value(data.trim());
+                }
+            } else if let Some(when) = line.strip_prefix("expire ") {
+                *expired = dhclient_time(when).is_some_and(|t| t < now);
+            }
+        }
+    }
+    latest.flatten()
+}
+
+/// `"http://wpad/wpad.dat"` (with C-style escapes) or colon-separated hex bytes.
+fn parse_dhclient_value(data: &str) -> Option<Vec<u8>> {
+    if let Some(quoted) = data.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"').unwrap_or(quoted);
+        return Some(unescape(quoted));
+    }
+    data.split(':')
+        .map(|b| u8::from_str_radix(b.trim(), 16).ok())
+        .collect()
+}
+
+fn unescape(quoted: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(quoted.len());
+    let bytes = quoted.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 1 < bytes.len() {
+            // dhclient 将不可打印字节写成三位八进制
+            let octal = bytes.get(i + 1..i + 4).filter(|digits| {
+                digits.iter().all(|b| (b'0'..=b'7').contains(b)) && digits[0] <= b'3'
+            });
+            if let Some(digits) = octal {
+                let byte = digits.iter().fold(0u8, |acc, d| acc * 8 + (d - b'0'));
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+            out.push(bytes[i + 1]);
+            i += 2;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// `4 2026/10/17 12:30:15` (UTC, leading weekday), `epoch 1792240215` or `never`.
+fn dhclient_time(when: &str) -> Option<NaiveDateTime> {
+    let mut fields = when.split_whitespace();
+    match fields.next()? {
+        "never" => None,
+        "epoch" => {
+            let secs = fields.next()?.parse().ok()?;
+            chrono::DateTime::from_timestamp(secs, 0).map(|t| t.naive_utc())
+        }
+        _ => {
+            let date_time = fields.collect::<Vec<_>>().join(" ");
+            NaiveDateTime::parse_from_str(&date_time, "%Y/%m/%d %H:%M:%S").ok()
+        }
+    }
+}
+
+// ---- systemd-networkd / NetworkManager internal client ----
+
+/// `OPTION_252=<hex>` holds the raw option bytes; some versions write `WPAD=<url>`.
+fn from_key_value(text: &str) -> Option<Vec<u8>> {
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        match key.trim() {
+            "OPTION_252" => return decode_hex(value),
+            "WPAD" => return Some(value.as_bytes().to_vec()),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// ---- dhcpcd (raw BOOTP/DHCP packet) ----
+
+const BOOTP_FIXED_LEN: usize = 236;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const OPTION_PAD: u8 = 0;
+const OPTION_END: u8 = 255;
+const OPTION_OVERLOAD: u8 = 52;
+
+/// The option areas of a DHCP packet: the options field, then the `file` and `sname`
+/// fields when option 52 says they carry options too (RFC 2131 §4.1).
+fn dhcp_packet_options(packet: &[u8]) -> Option<Vec<&[u8]>> {
+    let options_start = BOOTP_FIXED_LEN + MAGIC_COOKIE.len();
+    if packet.len() < options_start || packet[BOOTP_FIXED_LEN..options_start] != MAGIC_COOKIE {
+        return None;
+    }
+    let mut areas = vec![&packet[options_start..]];
+    let overload = find_option(&areas, OPTION_OVERLOAD)
+        .and_then(|value| value.first().copied())
+        .unwrap_or(0);
+    if overload & 1 != 0 {
+        areas.push(&packet[108..236]); // file
+    }
+    if overload & 2 != 0 {
+        areas.push(&packet[44..108]); // sname
+    }
+    Some(areas)
+}
+
+/// Value of option `code`, concatenating repeated instances (RFC 3396).
+fn find_option(areas: &[&[u8]], code: u8) -> Option<Vec<u8>> {
+    let mut value: Option<Vec<u8>> = None;
+    for area in areas {
+        let mut i = 0;
+        while let Some(&tag) = area.get(i) {
+            match tag {
+                OPTION_PAD => i += 1,
+                OPTION_END => break,
+                _ => {
+                    let len = area.get(i + 1).map_or(0, |len| usize::from(*len));
+                    // 截断的选项：忽略剩余部分
+                    let Some(data) = area.get(i + 2..i + 2 + len) else {
+                        break;
+                    };
+                    if tag == code {
+                        value.get_or_insert_with(Vec::new).extend_from_slice(data);
+                    }
+                    i += 2 + len;
+                }
+            }
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/leases")
+            .join(name)
+    }
+
+    fn wpad_from(name: &str) -> Option<String> {
+        wpad_url_from_lease(&fs::read(fixture(name)).unwrap())
+    }
+
+    #[test]
+    fn dhclient_uses_last_unexpired_lease() {
+        assert_eq!(
+            wpad_from("dhclient.eth0.leases").as_deref(),
+            Some("http://wpad.corp.example/wpad.dat")
+        );
+    }
+
+    #[test]
+    fn dhclient_without_option_declaration() {
+        // dhclient.conf 未声明 252 时写成十六进制 unknown-252
+        assert_eq!(
+            wpad_from("dhclient-unknown-252.lease").as_deref(),
+            Some("http://proxy.lab/proxy.pac")
+        );
+    }
+
+    #[test]
+    fn networkmanager_internal_client() {
+        assert_eq!(
+            wpad_from("internal-6f2c1d9e-3a8b-4c4e-9d2b-8c1f0a7e5b21-enp0s31f6.lease").as_deref(),
+            Some("http://wpad.branch.corp.example/proxy.pac")
+        );
+    }
+
+    #[test]
+    fn networkd_lease_without_option() {
+        assert_eq!(wpad_from("2"), None);
+    }
+
+    #[test]
+    fn dhcpcd_packet() {
+        assert_eq!(
+            wpad_from("eth0.lease").as_deref(),
+            Some("http://wpad.lab.example/wpad.dat")
+        );
+    }
+
+    #[test]
+    fn dhcpcd_packet_with_overloaded_file_field() {
+        assert_eq!(
+            wpad_from("wlan0-overload.lease").as_deref(),
+            Some("http://proxy.overload.example/proxy.pac")
+        );
+    }
+
+    #[test]
+    fn most_recent_lease_with_option_wins() {
+        let dir = std::env::temp_dir().join(format!("proxyparser-leases-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (name, age) in [("eth0.lease", 60), ("dhclient.eth0.leases", 10), ("2", 0)] {
+            let path = dir.join(name);
+            fs::copy(fixture(name), &path).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+        // 非租约文件不参与
+        fs::write(dir.join("NetworkManager.state"), "OPTION_252=00").unwrap();
+
+        let found = wpad_url_from_leases(&[dir.clone(), dir.join("missing")]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found.as_deref(), Some("http://wpad.corp.example/wpad.dat"));
+    }
+}
@@ -0,0 +1,153 @@
+// src/system_proxy/linux/gnome.rs
+//! The `org.gnome.system.proxy` schema and its `.http`, `.https`, `.ftp` and `.socks`
+//! children, as used by GNOME, Cinnamon, MATE, Budgie and GLib's proxy resolver.
+
+use super::gvariant::{self, GVariant};
+use crate::proxy::{ProxyDescriptor, ProxyServer};
+use crate::system_proxy::{ManualProxyConfig, SystemProxyConfig};
+use crate::{log_debug, log_info};
+use std::collections::HashMap;
+use std::process::Command;
+
+pub(crate) const SCHEMA: &str = "org.gnome.system.proxy";
+
+/// Values keyed by (schema, key), e.g. (`org.gnome.system.proxy.http`, `host`).
+pub(crate) type Settings = HashMap<(String, String), GVariant>;
+
+/// Reads every key of the proxy schemas with a single `gsettings list-recursively`.
+pub(crate) fn read_gsettings() -> Option<Settings> {
+    let output = Command::new("gsettings")
+        .args(["list-recursively", SCHEMA])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let settings = parse_list_recursively(&String::from_utf8_lossy(&output.stdout));
+    (!settings.is_empty()).then_some(settings)
+}
+
+/// Lines of `<schema> <key> <GVariant text>`.
+fn parse_list_recursively(output: &str) -> Settings {
+    let mut settings = Settings::new();
+    for line in output.lines() {
+        let mut fields = line.splitn(3, ' ');
+        let (Some(schema), Some(key), Some(text)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        match gvariant::parse_text(text) {
+            Some(value) => {
+                settings.insert((schema.to_string(), key.to_string()), value);
+            }
+            None => log_debug!("gsettings: cannot parse {} {} = {}", schema, key, text),
+        }
+    }
+    settings
+}
+
+/// Default values from the schema, for keys missing from `settings`.
+fn default_value(schema: &str, key: &str) -> Option<GVariant> {
+    let child = schema.strip_prefix(SCHEMA)?.trim_start_matches('.');
+    let value = match (child, key) {
+        ("", "mode") => GVariant::Str("none".to_string()),
+        ("", "autoconfig-url") => GVariant::Str(String::new()),
+        ("", "use-same-proxy") => GVariant::Bool(true),
+        ("", "ignore-hosts") => GVariant::Array(
+            ["localhost", "127.0.0.0/8", "::1"]
+                .iter()
+                .map(|host| GVariant::Str(host.to_string()))
+                .collect(),
+        ),
+        ("http", "port") => GVariant::Int(8080),
+        ("http", "use-authentication") => GVariant::Bool(false),
+        ("http", "authentication-user" | "authentication-password") => GVariant::Str(String::new()),
+        (_, "host") => GVariant::Str(String::new()),
+        (_, "port") => GVariant::Int(0),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Builds the configuration from the GNOME settings.
+///
+/// In manual mode each scheme uses its own proxy. When `use-same-proxy` is set, HTTPS
+/// and FTP fall back to the HTTP proxy; other schemes go through the SOCKS proxy.
+/// `ignore-hosts` takes host names, `*.domain` and other `*` globs (`*.corp*`), IP
+/// addresses, CIDR networks and partial IPv4 wildcards (`192.168.*`); see [`BypassList`].
+///
+/// [`BypassList`]: crate::hostname::BypassList
+pub(crate) fn proxy_config(settings: &Settings) -> SystemProxyConfig {
+    let value = |schema: &str, key: &str| {
+        settings
+            .get(&(schema.to_string(), key.to_string()))
+            .cloned()
+            .or_else(|| default_value(schema, key))
+    };
+    let string = |schema: &str, key: &str| {
+        value(schema, key)
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default()
+    };
+    let boolean = |schema: &str, key: &str| value(schema, key).and_then(|v| v.as_bool());
+
+    match string(SCHEMA, "mode").as_str() {
+        "manual" => {
+            let server = |child: &str| {
+                let schema = format!("{}.{}", SCHEMA, child);
+                let port = value(&schema, "port").and_then(|v| v.as_int()).unwrap_or(0);
+                proxy_server(&string(&schema, "host"), port)
+            };
+
+            let http_schema = format!("{}.http", SCHEMA);
+            let http = server("http").map(|server| {
+                let user = string(&http_schema, "authentication-user");
+                if boolean(&http_schema, "use-authentication") == Some(true) && !user.is_empty() {
+                    let password = string(&http_schema, "authentication-password");
+                    server.with_credentials(user, (!password.is_empty()).then_some(password))
+                } else {
+                    server
+                }
+            });
+            let http = http.map(ProxyDescriptor::Http);
+            let same_proxy = boolean(SCHEMA, "use-same-proxy") == Some(true);
+            let or_http = |proxy: Option<ProxyDescriptor>| {
+                proxy.or_else(|| http.clone().filter(|_| same_proxy))
+            };
+
+            let manual = ManualProxyConfig {
+                // HTTPS 流量同样经由 HTTP (CONNECT) 代理
+                https: or_http(server("https").map(ProxyDescriptor::Http)),
+                ftp: or_http(server("ftp").map(ProxyDescriptor::Http)),
+                socks: server("socks").map(ProxyDescriptor::Socks5),
+                http,
+                bypass: value(SCHEMA, "ignore-hosts")
+                    .and_then(|v| v.as_strs())
+                    .unwrap_or_default(),
+            };
+            log_info!("GNOME proxy mode is manual");
+            SystemProxyConfig::Manual(manual)
+        }
+        "auto" => {
+            let pac_url = string(SCHEMA, "autoconfig-url");
+            if pac_url.is_empty() {
+                // 自动模式但未配置 PAC URL：进行 WPAD 自动发现
+                log_info!("GNOME proxy mode is auto without a PAC URL");
+                return SystemProxyConfig::AutoDetect;
+            }
+            log_info!("Found GNOME PAC URL: {}", pac_url);
+            SystemProxyConfig::AutoConfigUrl(pac_url)
+        }
+        _ => SystemProxyConfig::Direct,
+    }
+}
+
+/// GNOME stores the bare host, but users also type `http://proxy.corp/` or `[::1]`.
+fn proxy_server(host: &str, port: i64) -> Option<ProxyServer> {
+    let host = host.trim();
+    let host = host.split_once("://").map_or(host, |(_, rest)| rest);
+    let host = host
+        .trim_end_matches('/')
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = u16::try_from(port).ok().filter(|port| *port > 0)?;
+    (!host.is_empty()).then(|| ProxyServer::new(host, port))
+}
@@ -0,0 +1,331 @@
+// src/system_proxy/linux/gvariant.rs
+//! The subset of GVariant needed for desktop proxy settings: booleans, integers,
+//! strings and arrays, parsed from the text format printed by `gsettings` or from the
+//! serialized form stored in dconf databases.
+
+/// A GVariant value of one of the types used by the proxy schemas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GVariant {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Array(Vec<GVariant>),
+}
+
+impl GVariant {
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            GVariant::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_int(&self) -> Option<i64> {
+        match self {
+            GVariant::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            GVariant::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_strs(&self) -> Option<Vec<String>> {
+        match self {
+            GVariant::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(String::from))
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Parses GVariant text such as `'manual'`, `"it's"`, `8080`, `uint32 8080`, `true`,
+/// `['localhost', '127.0.0.0/8']` or `@as []`. Returns `None` for anything else or for
+/// trailing garbage.
+pub(crate) fn parse_text(text: &str) -> Option<GVariant> {
+    let mut parser = Parser { rest: text };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    parser.rest.is_empty().then_some(value)
+}
+
+/// Decodes a serialized GVariant of type `v`: the value, a NUL byte and the type string
+/// of the value. Only little-endian data is supported.
+pub(crate) fn from_variant_bytes(data: &[u8]) -> Option<GVariant> {
+    let separator = data.iter().rposition(|b| *b == 0)?;
+    let signature = std::str::from_utf8(&data[separator + 1..]).ok()?;
+    decode(signature, &data[..separator])
+}
+
+fn decode(signature: &str, data: &[u8]) -> Option<GVariant> {
+    let int = |size: usize| -> Option<[u8; 8]> {
+        (data.len() == size).then(|| {
+            let mut bytes = [0; 8];
+            bytes[..size].copy_from_slice(data);
+            bytes
+        })
+    };
+    let value = match signature {
+        "b" => GVariant::Bool(int(1)?[0] != 0),
+        "y" => GVariant::Int(i64::from(int(1)?[0])),
+        "n" => GVariant::Int(i64::from(i16::from_le_bytes(data.try_into().ok()?))),
+        "q" => GVariant::Int(i64::from(u16::from_le_bytes(data.try_into().ok()?))),
+        "i" => GVariant::Int(i64::from(i32::from_le_bytes(data.try_into().ok()?))),
+        "u" => GVariant::Int(i64::from(u32::from_le_bytes(data.try_into().ok()?))),
+        "x" | "t" => GVariant::Int(i64::from_le_bytes(int(8)?)),
+        // 字符串以 NUL 结尾
+        "s" | "o" | "g" => {
+            let text = data.strip_suffix(&[0]).unwrap_or(data);
+            GVariant::Str(String::from_utf8(text.to_vec()).ok()?)
+        }
+        _ => {
+            let element = signature.strip_prefix('a')?;
+            GVariant::Array(decode_array(element, data)?)
+        }
+    };
+    Some(value)
+}
+
+/// Arrays of fixed-size elements are packed; arrays of strings end with a table of
+/// framing offsets, one per element, sized by the length of the array.
+fn decode_array(element: &str, data: &[u8]) -> Option<Vec<GVariant>> {
+    let fixed_size = match element {
+        "b" | "y" => Some(1),
+        "n" | "q" => Some(2),
+        "i" | "u" => Some(4),
+        "x" | "t" => Some(8),
+        "s" | "o" | "g" => None,
+        _ => return None,
+    };
+    if let Some(size) = fixed_size {
+        return data
+            .chunks(size)
+            .map(|chunk| decode(element, chunk))
+            .collect();
+    }
+    if data.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let offset_size = match data.len() {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        _ => 4,
+    };
+    let read_offset = |at: usize| -> Option<usize> {
+        let mut bytes = [0; 4];
+        bytes[..offset_size].copy_from_slice(data.get(at..at + offset_size)?);
+        Some(u32::from_le_bytes(bytes) as usize)
+    };
+    let offsets_start = read_offset(data.len() - offset_size)?;
+    let count = data.len().checked_sub(offsets_start)? / offset_size;
+    let mut items = Vec::with_capacity(count);
+    let mut start = 0;
+    for index in 0..count {
+        let end = read_offset(offsets_start + index * offset_size)?;
+        items.push(decode(element, data.get(start..end)?)?);
+        start = end;
+    }
+    Some(items)
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+/// Type keywords that may precede a number (`uint32 7`).
+const NUMBER_TYPES: [&str; 7] = [
+    "byte", "int16", "uint16", "int32", "uint32", "int64", "uint64",
+];
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self) -> Option<GVariant> {
+        self.skip_whitespace();
+        // 类型注解：@as []、uint32 8080
+        if let Some(rest) = self.rest.strip_prefix('@') {
+            let end = rest.find(char::is_whitespace)?;
+            self.rest = &rest[end..];
+            return self.value();
+        }
+        for keyword in NUMBER_TYPES {
+            if let Some(rest) = self.rest.strip_prefix(keyword) {
+                if rest.starts_with(char::is_whitespace) {
+                    self.rest = rest;
+                    return self.value();
+                }
+            }
+        }
+
+        match self.rest.chars().next()? {
+            '\'' | '"' => self.string().map(GVariant::Str),
+            '[' => self.array(),
+            '-' | '0'..='9' => self.number(),
+            _ if self.eat("true") => Some(GVariant::Bool(true)),
+            _ if self.eat("false") => Some(GVariant::Bool(false)),
+            _ => None,
+        }
+    }
+
+    fn array(&mut self) -> Option<GVariant> {
+        self.eat("[");
+        let mut items = Vec::new();
+        if self.eat("]") {
+            return Some(GVariant::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.eat("]") {
+                return Some(GVariant::Array(items));
+            }
+            if !self.eat(",") {
+                return None;
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<GVariant> {
+        let end = self
+            .rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_digit())
+            .map_or(self.rest.len(), |(i, _)| i);
+        let number = self.rest[..end].parse().ok()?;
+        self.rest = &self.rest[end..];
+        Some(GVariant::Int(number))
+    }
+
+    /// A single- or double-quoted string with GLib's escapes (`\'`, `\n`, `\u00e9`, ...).
+    fn string(&mut self) -> Option<String> {
+        let mut chars = self.rest.char_indices();
+        let (_, quote) = chars.next()?;
+        let mut out = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                _ if c == quote => {
+                    self.rest = &self.rest[i + c.len_utf8()..];
+                    return Some(out);
+                }
+                '\\' => {
+                    let (_, escaped) = chars.next()?;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'a' => out.push('\u{7}'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'v' => out.push('\u{b}'),
+                        'u' | 'U' => {
+                            let digits = if escaped == 'u' { 4 } else { 8 };
+                            let hex: String = chars.by_ref().take(digits).map(|(_, c)| c).collect();
+                            let code = u32::from_str_radix(&hex, 16).ok()?;
+                            out.push(char::from_u32(code)?);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strs(items: &[&str]) -> GVariant {
+        GVariant::Array(items.iter().map(|s| GVariant::Str(s.to_string())).collect())
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(
+            parse_text("'manual'"),
+            Some(GVariant::Str("manual".to_string()))
+        );
+        assert_eq!(parse_text("8080"), Some(GVariant::Int(8080)));
+        assert_eq!(parse_text("-1"), Some(GVariant::Int(-1)));
+        assert_eq!(parse_text("uint32 8080"), Some(GVariant::Int(8080)));
+        assert_eq!(parse_text("byte 7"), Some(GVariant::Int(7)));
+        assert_eq!(parse_text(" true "), Some(GVariant::Bool(true)));
+        assert_eq!(parse_text("false"), Some(GVariant::Bool(false)));
+    }
+
+    #[test]
+    fn escaped_strings() {
+        // gsettings 对含单引号的字符串改用双引号
+        assert_eq!(
+            parse_text(r#""it's""#),
+            Some(GVariant::Str("it's".to_string()))
+        );
+        assert_eq!(
+            parse_text(r"'it\'s'"),
+            Some(GVariant::Str("it's".to_string()))
+        );
+        assert_eq!(
+            parse_text(r#"'s3cr\\"et'"#),
+            Some(GVariant::Str(r#"s3cr\"et"#.to_string()))
+        );
+        assert_eq!(
+            parse_text(r"'tab\there\u00e9'"),
+            Some(GVariant::Str("tab\there\u{e9}".to_string()))
+        );
+        assert_eq!(parse_text("'unterminated"), None);
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(parse_text("@as []"), Some(strs(&[])));
+        assert_eq!(parse_text("[]"), Some(strs(&[])));
+        assert_eq!(
+            parse_text("['localhost', '127.0.0.0/8', \"it's\"]"),
+            Some(strs(&["localhost", "127.0.0.0/8", "it's"]))
+        );
+        assert_eq!(
+            parse_text("[['a', 'b'], @as [], ['c']]"),
+            Some(GVariant::Array(vec![
+                strs(&["a", "b"]),
+                strs(&[]),
+                strs(&["c"])
+            ]))
+        );
+        assert_eq!(
+            parse_text("[uint32 1, 2]"),
+            Some(GVariant::Array(vec![GVariant::Int(1), GVariant::Int(2)]))
+        );
+        assert_eq!(parse_text("['a', 'b'"), None);
+        assert_eq!(parse_text("['a' 'b']"), None);
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        assert_eq!(parse_text("'a' 'b'"), None);
+        assert_eq!(parse_text("8080x"), None);
+        assert_eq!(parse_text("@as"), None);
+        assert_eq!(parse_text(""), None);
+    }
+}
@@ -0,0 +1,115 @@
+// src/system_proxy/linux/gvdb.rs
+//! Reader for GVDB, the on-disk hash table format of dconf databases.
+//!
+//! A file starts with a 24-byte header (`GVariant` signature, version, options and a
+//! pointer to the root table). A table holds a bloom filter, hash buckets and 24-byte
+//! items; each item names its parent item, so full keys such as `/system/proxy/mode` are
+//! rebuilt by walking the parent chain. Values are serialized GVariants of type `v`.
+
+const SIGNATURE: &[u8; 8] = b"GVariant";
+const HEADER_LEN: usize = 24;
+const ITEM_LEN: usize = 24;
+const NO_PARENT: u32 = u32::MAX;
+
+/// A value stored in a table.
+pub(crate) enum Entry<'a> {
+    /// A serialized GVariant of type `v`.
+    Value(&'a [u8]),
+    /// A nested table, such as the `.locks` table of a system database.
+    Table(Table<'a>),
+    /// A directory listing; dconf writes one for every path prefix.
+    List,
+}
+
+/// One hash table of a GVDB file.
+pub(crate) struct Table<'a> {
+    data: &'a [u8],
+    items: Vec<Item>,
+}
+
+struct Item {
+    parent: u32,
+    key: (usize, usize),
+    kind: u8,
+    value: (usize, usize),
+}
+
+impl<'a> Table<'a> {
+    /// The root table of a GVDB file. Only little-endian files are supported, which is
+    /// what dconf writes on every common platform.
+    pub(crate) fn root(data: &'a [u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || &data[..8] != SIGNATURE || read_u32(data, 8)? != 0 {
+            return None;
+        }
+        Table::at(data, read_u32(data, 16)?, read_u32(data, 20)?)
+    }
+
+    fn at(data: &'a [u8], start: u32, end: u32) -> Option<Self> {
+        let table = data.get(start as usize..end as usize)?;
+        // 高 5 位为 bloom_shift
+        let n_bloom_words = (read_u32(table, 0)? & ((1 << 27) - 1)) as usize;
+        let n_buckets = read_u32(table, 4)? as usize;
+        let items_start = 8 + (n_bloom_words + n_buckets) * 4;
+        let items_bytes = table.get(items_start..)?;
+        let items = items_bytes
+            .chunks_exact(ITEM_LEN)
+            .map(|item| {
+                let key_start = read_u32(item, 8)? as usize;
+                let key_size = usize::from(u16::from_le_bytes([item[12], item[13]]));
+                Some(Item {
+                    parent: read_u32(item, 4)?,
+                    key: (key_start, key_start + key_size),
+                    kind: item[14],
+                    value: (read_u32(item, 16)? as usize, read_u32(item, 20)? as usize),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Table { data, items })
+    }
+
+    /// Every item with its full key. Items with broken pointers are skipped.
+    pub(crate) fn entries(&self) -> Vec<(String, Entry<'a>)> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let key = self.full_key(item)?;
+                let entry = match item.kind {
+                    b'v' => Entry::Value(self.data.get(item.value.0..item.value.1)?),
+                    b'H' => Entry::Table(Table::at(
+                        self.data,
+                        item.value.0 as u32,
+                        item.value.1 as u32,
+                    )?),
+                    b'L' => Entry::List,
+                    _ => return None,
+                };
+                Some((key, entry))
+            })
+            .collect()
+    }
+
+    fn full_key(&self, item: &Item) -> Option<String> {
+        let mut parts = Vec::new();
+        let mut current = item;
+        // 防止损坏文件中的父链成环
+        for _ in 0..=self.items.len() {
+            parts.push(self.data.get(current.key.0..current.key.1)?);
+            if current.parent == NO_PARENT {
+                let key: Vec<u8> = parts
+                    .iter()
+                    .rev()
+                    .flat_map(|part| part.iter())
+                    .copied()
+                    .collect();
+                return String::from_utf8(key).ok();
+            }
+            current = self.items.get(current.parent as usize)?;
+        }
+        None
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
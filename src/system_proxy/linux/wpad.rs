@@ -0,0 +1,165 @@
+// src/system_proxy/linux/wpad.rs
+//! DNS-based WPAD (Web Proxy Auto-Discovery) for desktops set to "automatic" without a
+//! PAC URL. Windows does the same inside `WinHttpGetProxyForUrl`.
+
+use crate::hostname::canonicalize_host;
+use crate::pac::DnsResolver;
+use crate::{log_debug, log_info};
+use std::fs;
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Looks for `wpad.<domain>` along the DNS search domains and returns the PAC URL
+/// (`http://wpad.<domain>/wpad.dat`) of the first candidate that resolves.
+pub(crate) fn discover_wpad_url() -> Option<String> {
+    let resolv_conf = match fs::read_to_string(RESOLV_CONF) {
+        Ok(contents) => contents,
+        Err(e) => {
+            log_debug!("WPAD: cannot read {}: {}", RESOLV_CONF, e);
+            return None;
+        }
+    };
+    let domains = search_domains(&resolv_conf);
+    if domains.is_empty() {
+        log_info!("WPAD: no search domain configured, skipping auto-discovery");
+        return None;
+    }
+
+    let resolver = DnsResolver::shared();
+    for candidate in wpad_candidates(&domains) {
+        log_debug!("WPAD: trying {}", candidate);
+        if !resolver.resolve(&candidate).is_empty() {
+            let pac_url = format!("http://{}/wpad.dat", candidate);
+            log_info!("WPAD: discovered {}", pac_url);
+            return Some(pac_url);
+        }
+    }
+    log_info!("WPAD: no wpad host found for {}", domains.join(", "));
+    None
+}
+
+/// Search domains from resolv.conf. As in glibc, `search` and `domain` override each
+/// other and the last one wins.
+pub(crate) fn search_domains(resolv_conf: &str) -> Vec<String> {
+    let mut domains = Vec::new();
+    for line in resolv_conf.lines() {
+        let line = line.split(['#', ';']).next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("search") | Some("domain") => {
+                domains = fields
+                    .map(canonicalize_host)
+                    .filter(|domain| !domain.is_empty())
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    domains
+}
+
+/// `wpad.<domain>` for each search domain and each of its parents, most specific first,
+/// stopping before the parent would be a public suffix: for `eng.corp.example.co.uk`
+/// this yields `wpad.eng.corp.example.co.uk`, `wpad.corp.example.co.uk` and
+/// `wpad.example.co.uk`, but never `wpad.co.uk`. Unlisted TLDs such as `.corp` count as
+/// public suffixes, so `wpad.corp` is never queried either.
+pub(crate) fn wpad_candidates(domains: &[String]) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+    for domain in domains {
+        let mut current = domain.as_str();
+        // domain_str 返回可注册域名；本身就是公共后缀时为 None
+        while let Some(registrable) = psl::domain_str(current) {
+            let candidate = format!("wpad.{}", current);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+            if current.len() <= registrable.len() {
+                break;
+            }
+            current = match current.split_once('.') {
+                Some((_, parent)) => parent,
+                None => break,
+            };
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(domains: &[&str]) -> Vec<String> {
+        let domains: Vec<String> = domains.iter().map(|d| d.to_string()).collect();
+        wpad_candidates(&domains)
+    }
+
+    #[test]
+    fn stops_before_public_suffix() {
+        assert_eq!(
+            candidates(&["eng.corp.example.co.uk"]),
+            [
+                "wpad.eng.corp.example.co.uk",
+                "wpad.corp.example.co.uk",
+                "wpad.example.co.uk",
+            ]
+        );
+        assert_eq!(candidates(&["example.com"]), ["wpad.example.com"]);
+        assert!(candidates(&["co.uk"]).is_empty());
+    }
+
+    #[test]
+    fn unlisted_tld_is_a_public_suffix() {
+        assert_eq!(
+            candidates(&["eng.acme.corp"]),
+            ["wpad.eng.acme.corp", "wpad.acme.corp"]
+        );
+        assert!(candidates(&["corp"]).is_empty());
+    }
+
+    #[test]
+    fn single_label_domains() {
+        assert!(candidates(&["localdomain"]).is_empty());
+        assert!(candidates(&["lan", "home"]).is_empty());
+    }
+
+    #[test]
+    fn search_list_order() {
+        assert_eq!(
+            candidates(&[
+                "a.eng.example.com",
+                "example.com",
+                "lan",
+                "sales.example.org"
+            ]),
+            [
+                "wpad.a.eng.example.com",
+                "wpad.eng.example.com",
+                "wpad.example.com",
+                "wpad.sales.example.org",
+                "wpad.example.org",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolv_conf_search_domains() {
+        let resolv_conf = "\
+# Generated by NetworkManager
+nameserver 10.0.0.53
+domain old.example
+search Eng.Example.COM. example.org ; trailing comment
+options ndots:2
+";
+        assert_eq!(
+            search_domains(resolv_conf),
+            ["eng.example.com", "example.org"]
+        );
+        // search 与 domain 互相覆盖，最后一行生效
+        assert_eq!(
+            search_domains("search a.example\ndomain b.example\n"),
+            ["b.example"]
+        );
+        assert!(search_domains("nameserver 127.0.0.53\n#search x.example\n").is_empty());
+    }
+}
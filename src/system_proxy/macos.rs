@@ -1,101 +1,155 @@
 // src/system_proxy/macos.rs
 
+#[cfg(target_os = "macos")]
+use super::{ManualProxyConfig, SystemProxyConfig};
+#[cfg(target_os = "macos")]
+use crate::proxy::ProxyDescriptor;
 #[cfg(target_os = "macos")]
 use crate::{log_info, log_warn};
+use core_foundation::array::{CFArrayGetCount, CFArrayGetValueAtIndex, CFArrayRef};
 #[cfg(target_os = "macos")]
 use core_foundation::base::{CFRelease, TCFType};
 use core_foundation::dictionary::{CFDictionaryGetValue, CFDictionaryRef};
 use core_foundation::number::{kCFNumberSInt32Type, CFNumberGetValue, CFNumberRef};
 use core_foundation::string::{CFString, CFStringRef};
 use scopeguard::defer;
+use std::os::raw::c_void;
 use std::ptr;
 
 // 使用 sys crate 的 FFI 接口（全局依赖，确保不 private）
 use system_configuration_sys::dynamic_store_copy_specific::SCDynamicStoreCopyProxies;
 
+const kSCPropNetProxiesProxyAutoConfigEnable: &str = "ProxyAutoConfigEnable";
 const kSCPropNetProxiesProxyAutoConfigURLString: &str = "ProxyAutoConfigURLString";
-const kSCPropNetProxiesHTTPSProxy: &str = "HTTPSProxy";
-const kSCPropNetProxiesHTTPSPort: &str = "HTTPSPort";
+const kSCPropNetProxiesProxyAutoDiscoveryEnable: &str = "ProxyAutoDiscoveryEnable";
+const kSCPropNetProxiesHTTPEnable: &str = "HTTPEnable";
 const kSCPropNetProxiesHTTPProxy: &str = "HTTPProxy";
 const kSCPropNetProxiesHTTPPort: &str = "HTTPPort";
+const kSCPropNetProxiesHTTPSEnable: &str = "HTTPSEnable";
+const kSCPropNetProxiesHTTPSProxy: &str = "HTTPSProxy";
+const kSCPropNetProxiesHTTPSPort: &str = "HTTPSPort";
+const kSCPropNetProxiesFTPEnable: &str = "FTPEnable";
+const kSCPropNetProxiesFTPProxy: &str = "FTPProxy";
+const kSCPropNetProxiesFTPPort: &str = "FTPPort";
+const kSCPropNetProxiesSOCKSEnable: &str = "SOCKSEnable";
 const kSCPropNetProxiesSOCKSProxy: &str = "SOCKSProxy";
 const kSCPropNetProxiesSOCKSPort: &str = "SOCKSPort";
+const kSCPropNetProxiesExceptionsList: &str = "ExceptionsList";
+const kSCPropNetProxiesExcludeSimpleHostnames: &str = "ExcludeSimpleHostnames";
 
 #[cfg(target_os = "macos")]
-pub fn get_macos_proxy(_url: &str) -> Option<String> {
-    let pac_key = CFString::new(kSCPropNetProxiesProxyAutoConfigURLString);
-    let https_proxy_key = CFString::new(kSCPropNetProxiesHTTPSProxy);
-    let https_port_key = CFString::new(kSCPropNetProxiesHTTPSPort);
-    let http_proxy_key = CFString::new(kSCPropNetProxiesHTTPProxy);
-    let http_port_key = CFString::new(kSCPropNetProxiesHTTPPort);
-    let socks_proxy_key = CFString::new(kSCPropNetProxiesSOCKSProxy);
-    let socks_port_key = CFString::new(kSCPropNetProxiesSOCKSPort);
-
+pub fn get_macos_proxy_config() -> SystemProxyConfig {
     let proxies_dict: CFDictionaryRef = unsafe { SCDynamicStoreCopyProxies(ptr::null()) };
     if proxies_dict.is_null() {
         log_warn!("Failed to retrieve macOS proxy settings");
-        return None;
+        return SystemProxyConfig::Direct;
     }
 
     defer! { unsafe { CFRelease(proxies_dict as *const _) }; }
 
+    let get_value = |key: &str| -> Option<*const c_void> {
+        let key = CFString::new(key);
+        let value =
+            unsafe { CFDictionaryGetValue(proxies_dict, key.as_concrete_TypeRef() as *const _) };
+        (!value.is_null()).then_some(value)
+    };
+    let get_string = |key: &str| -> Option<String> {
+        let value = get_value(key)?;
+        Some(unsafe { CFString::wrap_under_get_rule(value as CFStringRef) }.to_string())
+    };
+    let get_number = |key: &str| -> Option<i32> {
+        let value = get_value(key)?;
+        let mut num: i32 = 0;
+        let ok = unsafe {
+            CFNumberGetValue(
+                value as CFNumberRef,
+                kCFNumberSInt32Type,
+                &mut num as *mut i32 as *mut c_void,
+            )
+        };
+        ok.then_some(num)
+    };
+    let enabled = |key: &str| get_number(key).is_some_and(|num| num != 0);
+
     // 检查 PAC URL
-    let pac_val: *const std::os::raw::c_void =
-        unsafe { CFDictionaryGetValue(proxies_dict, pac_key.as_concrete_TypeRef() as *const _) };
-    if !pac_val.is_null() {
-        let pac_url = unsafe { CFString::wrap_under_get_rule(pac_val as CFStringRef) };
-        let pac_str = pac_url.to_string();
-        if !pac_str.is_empty() {
-            log_info!("Found PAC URL: {}", pac_str);
-            return Some(pac_str);
+    if enabled(kSCPropNetProxiesProxyAutoConfigEnable) {
+        if let Some(pac_url) = get_string(kSCPropNetProxiesProxyAutoConfigURLString) {
+            if !pac_url.is_empty() {
+                log_info!("Found PAC URL: {}", pac_url);
+                return SystemProxyConfig::AutoConfigUrl(pac_url);
+            }
         }
     }
 
-    let get_host_port = |host_key: &CFString, port_key: &CFString| -> Option<String> {
-        let host_val: *const std::os::raw::c_void = unsafe {
-            CFDictionaryGetValue(proxies_dict, host_key.as_concrete_TypeRef() as *const _)
-        };
-        if host_val.is_null() {
+    let get_proxy = |enable_key: &str, host_key: &str, port_key: &str, scheme: &str| {
+        if !enabled(enable_key) {
             return None;
         }
-        let host = unsafe { CFString::wrap_under_get_rule(host_val as CFStringRef) }.to_string();
-
-        let port_val: *const std::os::raw::c_void = unsafe {
-            CFDictionaryGetValue(proxies_dict, port_key.as_concrete_TypeRef() as *const _)
+        let host = get_string(host_key).filter(|host| !host.is_empty())?;
+        let server = match get_number(port_key).filter(|port| *port > 0) {
+            Some(port) => format!("{}://{}:{}", scheme, host, port),
+            None => format!("{}://{}", scheme, host),
         };
-        let port = if !port_val.is_null() {
-            let mut num: i32 = 0;
-            let ok = unsafe {
-                CFNumberGetValue(
-                    port_val as CFNumberRef,
-                    kCFNumberSInt32Type,
-                    &mut num as *mut i32 as *mut std::os::raw::c_void,
-                )
-            };
-            if ok && num > 0 {
-                format!(":{}", num)
-            } else {
-                String::new()
+        match server.parse::<ProxyDescriptor>() {
+            Ok(proxy) => Some(proxy),
+            Err(e) => {
+                log_warn!("Ignoring system proxy: {}", e);
+                None
             }
-        } else {
-            String::new()
-        };
-
-        Some(format!("{}{}", host, port))
+        }
     };
 
-    if let Some(host_port) = get_host_port(&https_proxy_key, &https_port_key) {
-        log_info!("Found HTTPS proxy: https://{}", host_port);
-        return Some(format!("https://{}", host_port));
-    }
-    if let Some(host_port) = get_host_port(&http_proxy_key, &http_port_key) {
-        log_info!("Found HTTP proxy: http://{}", host_port);
-        return Some(format!("http://{}", host_port));
+    let mut manual = ManualProxyConfig {
+        http: get_proxy(
+            kSCPropNetProxiesHTTPEnable,
+            kSCPropNetProxiesHTTPProxy,
+            kSCPropNetProxiesHTTPPort,
+            "http",
+        ),
+        // HTTPSProxy 是用于 HTTPS 流量的普通 HTTP (CONNECT) 代理，而不是 TLS 代理
+        https: get_proxy(
+            kSCPropNetProxiesHTTPSEnable,
+            kSCPropNetProxiesHTTPSProxy,
+            kSCPropNetProxiesHTTPSPort,
+            "http",
+        ),
+        // FTPProxy 可能是原生 FTP 网关；ProxyDescriptor 没有 FTP 方案，只能按 http 报告
+        ftp: get_proxy(
+            kSCPropNetProxiesFTPEnable,
+            kSCPropNetProxiesFTPProxy,
+            kSCPropNetProxiesFTPPort,
+            "http",
+        ),
+        socks: get_proxy(
+            kSCPropNetProxiesSOCKSEnable,
+            kSCPropNetProxiesSOCKSProxy,
+            kSCPropNetProxiesSOCKSPort,
+            "socks5",
+        ),
+        bypass: Vec::new(),
+    };
+    if !manual.is_empty() {
+        if let Some(exceptions) = get_value(kSCPropNetProxiesExceptionsList) {
+            let exceptions = exceptions as CFArrayRef;
+            let count = unsafe { CFArrayGetCount(exceptions) };
+            for index in 0..count {
+                let entry = unsafe { CFArrayGetValueAtIndex(exceptions, index) };
+                if !entry.is_null() {
+                    let entry = unsafe { CFString::wrap_under_get_rule(entry as CFStringRef) };
+                    manual.bypass.push(entry.to_string());
+                }
+            }
+        }
+        if enabled(kSCPropNetProxiesExcludeSimpleHostnames) {
+            manual.bypass.push("<local>".to_string());
+        }
+        log_info!("Found manual proxies: {}", manual);
+        return SystemProxyConfig::Manual(manual);
     }
-    if let Some(host_port) = get_host_port(&socks_proxy_key, &socks_port_key) {
-        log_info!("Found SOCKS proxy: socks5://{}", host_port);
-        return Some(format!("socks5://{}", host_port));
+
+    if enabled(kSCPropNetProxiesProxyAutoDiscoveryEnable) {
+        return SystemProxyConfig::AutoDetect;
     }
 
-    None
+    SystemProxyConfig::Direct
 }
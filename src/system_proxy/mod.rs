@@ -9,26 +9,112 @@ pub mod linux;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
+mod config;
+
+pub use config::{ManualProxyConfig, SystemProxyConfig};
+
+use crate::pac;
+use crate::proxy::ProxyDescriptor;
+use crate::{log_info, log_warn};
+use url::Url;
+
 /// 统一获取当前平台的系统代理
 /// 返回值：
-/// - Some(String)：手动代理（如 "http://127.0.0.1:8080"）或 PAC URL（如 "http://.../proxy.pac"）
-/// - None：无代理配置或不支持
-pub fn get_system_proxy(url: &str) -> Option<String> {
+/// - Some(ProxyDescriptor)：手动代理，或 PAC 脚本为该 URL 选出的第一个代理
+/// - None：无代理配置、不支持，或 PAC 执行失败
+pub fn get_system_proxy(url: &str) -> Option<ProxyDescriptor> {
+    get_system_proxy_chain(url)?
+        .into_iter()
+        .find(|proxy| !proxy.is_direct())
+        .or(Some(ProxyDescriptor::Direct))
+}
+
+/// 与 [`get_system_proxy`] 相同，但 PAC 配置时返回完整的有序回退链（保留 DIRECT）
+pub fn get_system_proxy_chain(url: &str) -> Option<Vec<ProxyDescriptor>> {
+    proxy_chain(get_system_proxy_config(), url)
+}
+
+fn proxy_chain(config: SystemProxyConfig, url: &str) -> Option<Vec<ProxyDescriptor>> {
+    let pac_location = match config {
+        SystemProxyConfig::Direct => return None,
+        SystemProxyConfig::Manual(manual) => {
+            let target = Url::parse(url).ok()?;
+            return match manual.proxy_for(&target) {
+                Some(proxy) => Some(vec![proxy.clone()]),
+                // 命中例外列表：系统明确要求直连
+                None if !manual.is_empty() => Some(vec![ProxyDescriptor::Direct]),
+                None => None,
+            };
+        }
+        SystemProxyConfig::AutoConfigUrl(location) => location,
+        SystemProxyConfig::AutoDetect => match discover_pac_url() {
+            Some(location) => location,
+            None => return proxy_chain(auto_detect_fallback(), url),
+        },
+    };
+
+    log_info!("Detected PAC configuration: {}", pac_location);
+    match pac::evaluate_pac_chain_for_url(&pac_location, url) {
+        Ok(chain) => Some(chain),
+        Err(e) => {
+            log_warn!("PAC evaluation failed: {}", e);
+            None
+        }
+    }
+}
+
+/// The system proxy settings of the current platform, without evaluating any PAC.
+///
+/// ```no_run
+/// use proxyparser::system_proxy::{get_system_proxy_config, SystemProxyConfig};
+///
+/// match get_system_proxy_config() {
+///     SystemProxyConfig::AutoConfigUrl(pac) => println!("PAC script at {}", pac),
+///     config => println!("{}", config),
+/// }
+/// ```
+pub fn get_system_proxy_config() -> SystemProxyConfig {
     #[cfg(target_os = "macos")]
     {
-        macos::get_macos_proxy(url)
+        macos::get_macos_proxy_config()
     }
     #[cfg(target_os = "linux")]
     {
-        linux::get_linux_proxy(url)
+        linux::get_linux_proxy_config()
     }
     #[cfg(target_os = "windows")]
     {
-        windows::get_windows_proxy(url)
+        windows::get_windows_proxy_config()
     }
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
-        let _ = url; // 未使用参数警告消除
-        None
+        SystemProxyConfig::Direct
+    }
+}
+
+/// 自动检测未找到 PAC 时的回退配置：Windows 与 WinHTTP 一致，依次使用 PAC URL 和手动代理
+fn auto_detect_fallback() -> SystemProxyConfig {
+    #[cfg(target_os = "windows")]
+    {
+        windows::get_windows_fallback_config()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        SystemProxyConfig::Direct
+    }
+}
+
+/// WPAD：在网络上查找 PAC URL
+fn discover_pac_url() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    let pac_url = linux::discover_wpad_url();
+    #[cfg(target_os = "windows")]
+    let pac_url = windows::discover_wpad_url();
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    let pac_url = None;
+
+    if pac_url.is_none() {
+        log_info!("Proxy auto-detection found no PAC URL");
     }
+    pac_url
 }
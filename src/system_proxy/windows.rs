@@ -1,133 +1,174 @@
 // src/system_proxy/windows.rs
 
+#[cfg(target_os = "windows")]
+use super::{ManualProxyConfig, SystemProxyConfig};
+#[cfg(target_os = "windows")]
+use crate::proxy::ProxyDescriptor;
 #[cfg(target_os = "windows")]
 use crate::{log_info, log_warn};
-use std::ffi::{OsStr, OsString};
-use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
 use std::ptr;
 use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::winhttp::{
-    WinHttpCloseHandle, WinHttpGetIEProxyConfigForCurrentUser, WinHttpGetProxyForUrl, WinHttpOpen,
-    WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_AUTOPROXY_OPTIONS, WINHTTP_AUTO_DETECT_TYPE_DHCP,
-    WINHTTP_AUTO_DETECT_TYPE_DNS_A, WINHTTP_CURRENT_USER_IE_PROXY_CONFIG, WINHTTP_PROXY_INFO,
+    WinHttpDetectAutoProxyConfigUrl, WinHttpGetIEProxyConfigForCurrentUser,
+    WINHTTP_AUTO_DETECT_TYPE_DHCP, WINHTTP_AUTO_DETECT_TYPE_DNS_A,
+    WINHTTP_CURRENT_USER_IE_PROXY_CONFIG,
 };
 
+/// The IE proxy settings of the current user. WinHTTP and IE try them in this order:
+/// auto-detection, then the PAC URL, then the manual proxy.
 #[cfg(target_os = "windows")]
-pub fn get_windows_proxy(url: &str) -> Option<String> {
-    let url_wide: Vec<u16> = OsStr::new(url)
-        .encode_wide()
-        .chain(std::iter::once(0))
-        .collect();
-
-    // 第一步：读取 IE 代理配置（手动代理或 PAC URL）
-    let mut ie_config = WINHTTP_CURRENT_USER_IE_PROXY_CONFIG {
-        fAutoDetect: 0,
-        lpszAutoConfigUrl: ptr::null_mut(),
-        lpszProxy: ptr::null_mut(),
-        lpszProxyBypass: ptr::null_mut(),
-    };
+struct IeProxyConfig {
+    auto_detect: bool,
+    pac_url: String,
+    proxy: String,
+    bypass: String,
+}
 
-    let success = unsafe { WinHttpGetIEProxyConfigForCurrentUser(&mut ie_config) };
-    if success == 0 {
-        log_warn!("WinHttpGetIEProxyConfigForCurrentUser failed: {}", unsafe {
-            GetLastError()
-        });
-        return None;
-    }
+#[cfg(target_os = "windows")]
+impl IeProxyConfig {
+    fn read() -> Option<Self> {
+        let mut ie_config = WINHTTP_CURRENT_USER_IE_PROXY_CONFIG {
+            fAutoDetect: 0,
+            lpszAutoConfigUrl: ptr::null_mut(),
+            lpszProxy: ptr::null_mut(),
+            lpszProxyBypass: ptr::null_mut(),
+        };
 
-    let _guard = scopeguard::guard((), |_| unsafe {
-        if !ie_config.lpszAutoConfigUrl.is_null() {
-            winapi::um::winbase::GlobalFree(ie_config.lpszAutoConfigUrl as _);
-        }
-        if !ie_config.lpszProxy.is_null() {
-            winapi::um::winbase::GlobalFree(ie_config.lpszProxy as _);
-        }
-        if !ie_config.lpszProxyBypass.is_null() {
-            winapi::um::winbase::GlobalFree(ie_config.lpszProxyBypass as _);
-        }
-    });
-
-    // 如果有 PAC URL，直接返回
-    if !ie_config.lpszAutoConfigUrl.is_null() {
-        let pac_url = wide_ptr_to_string(ie_config.lpszAutoConfigUrl);
-        if !pac_url.is_empty() {
-            log_info!("Found PAC URL from IE config: {}", pac_url);
-            return Some(pac_url);
+        let success = unsafe { WinHttpGetIEProxyConfigForCurrentUser(&mut ie_config) };
+        if success == 0 {
+            log_warn!("WinHttpGetIEProxyConfigForCurrentUser failed: {}", unsafe {
+                GetLastError()
+            });
+            return None;
         }
+
+        let _guard = scopeguard::guard((), |_| unsafe {
+            if !ie_config.lpszAutoConfigUrl.is_null() {
+                winapi::um::winbase::GlobalFree(ie_config.lpszAutoConfigUrl as _);
+            }
+            if !ie_config.lpszProxy.is_null() {
+                winapi::um::winbase::GlobalFree(ie_config.lpszProxy as _);
+            }
+            if !ie_config.lpszProxyBypass.is_null() {
+                winapi::um::winbase::GlobalFree(ie_config.lpszProxyBypass as _);
+            }
+        });
+
+        Some(IeProxyConfig {
+            auto_detect: ie_config.fAutoDetect != 0,
+            pac_url: wide_ptr_to_string(ie_config.lpszAutoConfigUrl),
+            proxy: wide_ptr_to_string(ie_config.lpszProxy),
+            bypass: wide_ptr_to_string(ie_config.lpszProxyBypass),
+        })
     }
 
-    // 如果有手动代理
-    if !ie_config.lpszProxy.is_null() {
-        let proxy = wide_ptr_to_string(ie_config.lpszProxy);
-        if !proxy.is_empty() {
-            log_info!("Found manual proxy from IE config: {}", proxy);
-            return Some(format!("http://{}", proxy));
+    fn to_config(&self) -> SystemProxyConfig {
+        if self.auto_detect {
+            return SystemProxyConfig::AutoDetect;
         }
+        self.without_auto_detect()
     }
 
-    // 如果开启自动检测，尝试 WPAD
-    if ie_config.fAutoDetect != 0 {
-        let session = unsafe {
-            WinHttpOpen(
-                ptr::null(),
-                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
-                ptr::null(),
-                ptr::null(),
-                0,
-            )
-        };
-        if session.is_null() {
-            log_warn!("WinHttpOpen failed for WPAD");
-            return None;
+    /// The PAC URL, else the manual proxy, else direct.
+    fn without_auto_detect(&self) -> SystemProxyConfig {
+        if !self.pac_url.is_empty() {
+            log_info!("Found PAC URL from IE config: {}", self.pac_url);
+            return SystemProxyConfig::AutoConfigUrl(self.pac_url.clone());
         }
 
-        let mut auto_options = WINHTTP_AUTOPROXY_OPTIONS {
-            dwFlags: WINHTTP_AUTO_DETECT_TYPE_DHCP | WINHTTP_AUTO_DETECT_TYPE_DNS_A,
-            dwAutoDetectFlags: 0,
-            lpszAutoConfigUrl: ptr::null_mut(),
-            lpvReserved: ptr::null_mut(),
-            dwReserved: 0,
-            fAutoLogonIfChallenged: 1,
-        };
+        if !self.proxy.is_empty() {
+            log_info!("Found manual proxy from IE config: {}", self.proxy);
+            let mut manual = parse_proxy_list(&self.proxy);
+            manual.bypass = self
+                .bypass
+                .split(|c: char| c == ';' || c.is_whitespace())
+                .filter(|entry| !entry.is_empty())
+                .map(String::from)
+                .collect();
+            return SystemProxyConfig::Manual(manual);
+        }
 
-        let mut proxy_info = WINHTTP_PROXY_INFO {
-            dwAccessType: 0,
-            lpszProxy: ptr::null_mut(),
-            lpszProxyBypass: ptr::null_mut(),
-        };
+        SystemProxyConfig::Direct
+    }
+}
 
-        let result = unsafe {
-            WinHttpGetProxyForUrl(
-                session,
-                url_wide.as_ptr(),
-                &mut auto_options,
-                &mut proxy_info,
-            )
-        };
+#[cfg(target_os = "windows")]
+pub fn get_windows_proxy_config() -> SystemProxyConfig {
+    // 读取 IE 代理配置（自动检测、PAC URL 或手动代理）
+    IeProxyConfig::read().map_or(SystemProxyConfig::Direct, |ie| ie.to_config())
+}
 
-        unsafe { WinHttpCloseHandle(session) };
+/// What WinHTTP uses when auto-detection finds no PAC URL: the configured PAC URL,
+/// then the manual proxy.
+#[cfg(target_os = "windows")]
+pub(crate) fn get_windows_fallback_config() -> SystemProxyConfig {
+    IeProxyConfig::read().map_or(SystemProxyConfig::Direct, |ie| ie.without_auto_detect())
+}
 
-        if result != 0 {
-            let _proxy_guard = scopeguard::guard((), |_| unsafe {
-                if !proxy_info.lpszProxy.is_null() {
-                    winapi::um::winbase::GlobalFree(proxy_info.lpszProxy as _);
-                }
-                if !proxy_info.lpszProxyBypass.is_null() {
-                    winapi::um::winbase::GlobalFree(proxy_info.lpszProxyBypass as _);
-                }
-            });
+/// WPAD through DHCP and DNS, as done by WinHTTP.
+#[cfg(target_os = "windows")]
+pub(crate) fn discover_wpad_url() -> Option<String> {
+    let mut pac_url: *mut u16 = ptr::null_mut();
+    let found = unsafe {
+        WinHttpDetectAutoProxyConfigUrl(
+            WINHTTP_AUTO_DETECT_TYPE_DHCP | WINHTTP_AUTO_DETECT_TYPE_DNS_A,
+            &mut pac_url,
+        )
+    };
+    if found == 0 {
+        log_info!(
+            "WinHttpDetectAutoProxyConfigUrl found nothing: {}",
+            unsafe { GetLastError() }
+        );
+        return None;
+    }
+    let url = wide_ptr_to_string(pac_url);
+    unsafe { winapi::um::winbase::GlobalFree(pac_url as _) };
+    log_info!("Found PAC URL via WPAD: {}", url);
+    (!url.is_empty()).then_some(url)
+}
 
-            if !proxy_info.lpszProxy.is_null() {
-                let proxy = wide_ptr_to_string(proxy_info.lpszProxy);
-                if !proxy.is_empty() {
-                    log_info!("Found proxy via WPAD: {}", proxy);
-                    return Some(format!("http://{}", proxy));
-                }
+/// `proxy:8080` for every scheme, or per scheme: `http=a:8080;https=b:8443;socks=c:1080`.
+#[cfg(target_os = "windows")]
+fn parse_proxy_list(list: &str) -> ManualProxyConfig {
+    let mut manual = ManualProxyConfig::default();
+    for entry in list
+        .split(|c: char| c == ';' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+    {
+        let (scheme, server) = match entry.split_once('=') {
+            Some((scheme, server)) => (scheme.to_ascii_lowercase(), server),
+            None => (String::new(), entry),
+        };
+        let proxy_scheme = if scheme == "socks" { "socks4" } else { "http" };
+        let server = if server.contains("://") {
+            server.to_string()
+        } else {
+            format!("{}://{}", proxy_scheme, server)
+        };
+        let proxy: ProxyDescriptor = match server.parse() {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                log_warn!("Ignoring system proxy: {}", e);
+                continue;
+            }
+        };
+        match scheme.as_str() {
+            "" => {
+                manual.http = Some(proxy.clone());
+                manual.https = Some(proxy.clone());
+                manual.ftp = Some(proxy);
             }
+            "http" => manual.http = Some(proxy),
+            "https" => manual.https = Some(proxy),
+            "ftp" => manual.ftp = Some(proxy),
+            "socks" => manual.socks = Some(proxy),
+            other => log_warn!("Ignoring proxy for unknown scheme {}", other),
         }
     }
-
-    None
+    manual
 }
 
 #[cfg(target_os = "windows")]
@@ -139,3 +180,50 @@ fn wide_ptr_to_string(ptr: *mut u16) -> String {
     let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
     OsString::from_wide(slice).to_string_lossy().into_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ie(auto_detect: bool, pac_url: &str, proxy: &str) -> IeProxyConfig {
+        IeProxyConfig {
+            auto_detect,
+            pac_url: pac_url.to_string(),
+            proxy: proxy.to_string(),
+            bypass: "<local>;*.corp".to_string(),
+        }
+    }
+
+    #[test]
+    fn auto_detect_comes_first() {
+        let all = ie(true, "http://wpad.corp/proxy.pac", "proxy.corp:8080");
+        assert_eq!(all.to_config(), SystemProxyConfig::AutoDetect);
+        // 自动检测失败后依次回退到 PAC URL 和手动代理
+        assert_eq!(
+            all.without_auto_detect(),
+            SystemProxyConfig::AutoConfigUrl("http://wpad.corp/proxy.pac".to_string())
+        );
+        let manual = ie(true, "", "proxy.corp:8080").without_auto_detect();
+        match manual {
+            SystemProxyConfig::Manual(manual) => {
+                assert_eq!(
+                    manual.http.map(|p| p.to_string()).as_deref(),
+                    Some("http://proxy.corp:8080")
+                );
+                assert_eq!(manual.bypass, ["<local>", "*.corp"]);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(ie(false, "", "").to_config(), SystemProxyConfig::Direct);
+    }
+
+    #[test]
+    fn proxy_list() {
+        let manual = parse_proxy_list("http=a:8080;https=b:8443 socks=c:1080");
+        let proxy = |p: Option<ProxyDescriptor>| p.map(|p| p.to_string());
+        assert_eq!(proxy(manual.http).as_deref(), Some("http://a:8080"));
+        assert_eq!(proxy(manual.https).as_deref(), Some("http://b:8443"));
+        assert_eq!(proxy(manual.ftp), None);
+        assert_eq!(proxy(manual.socks).as_deref(), Some("socks4://c:1080"));
+    }
+}
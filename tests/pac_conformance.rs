@@ -0,0 +1,245 @@
+// tests/pac_conformance.rs
+//! PAC helper behaviour checked against Mozilla's implementation and the examples
+//! published on MDN ("Proxy Auto-Configuration (PAC) file").
+
+use chrono::DateTime;
+use proxyparser::pac::{FixedHost, PacEngine, PacOptions};
+use std::sync::Arc;
+
+/// Saturday 2026-10-17 14:30:15 at UTC+02:00 (12:30:15 GMT).
+fn host() -> FixedHost {
+    FixedHost::new(DateTime::parse_from_rfc3339("2026-10-17T14:30:15+02:00").unwrap())
+        .with_local_address("192.168.1.20".parse().unwrap())
+        .with_local_address("2001:db8::20".parse().unwrap())
+        .with_dns("www.mozilla.org", ["198.95.249.79".parse().unwrap()])
+        .with_dns(
+            "dual.example",
+            [
+                "2001:db8:1::7".parse().unwrap(),
+                "10.1.2.3".parse().unwrap(),
+            ],
+        )
+}
+
+/// Evaluates `expr` inside `FindProxyForURL` and returns it converted to a string.
+fn eval(expr: &str) -> String {
+    let script = format!(
+        "function FindProxyForURL(url, host) {{ return String({}); }}",
+        expr
+    );
+    let options = PacOptions {
+        host: Arc::new(host()),
+        ..PacOptions::default()
+    };
+    let mut engine = PacEngine::with_options(&script, options).unwrap();
+    engine.find_proxy_raw("http://www.mozilla.org/").unwrap()
+}
+
+fn assert_eval(cases: &[(&str, &str)]) {
+    for (expr, expected) in cases {
+        assert_eq!(eval(expr), *expected, "{}", expr);
+    }
+}
+
+#[test]
+fn is_plain_host_name() {
+    assert_eval(&[
+        (r#"isPlainHostName("www")"#, "true"),
+        (r#"isPlainHostName("www.mozilla.org")"#, "false"),
+        (r#"isPlainHostName("www.")"#, "false"),
+        (r#"isPlainHostName("")"#, "true"),
+        // IP literals are never plain host names
+        (r#"isPlainHostName("10.1.2.3")"#, "false"),
+        (r#"isPlainHostName("::1")"#, "false"),
+        (r#"isPlainHostName("fe80::1")"#, "false"),
+    ]);
+}
+
+#[test]
+fn dns_domain_is() {
+    assert_eval(&[
+        (r#"dnsDomainIs("www.mozilla.org", ".mozilla.org")"#, "true"),
+        (r#"dnsDomainIs("www", ".mozilla.org")"#, "false"),
+        (r#"dnsDomainIs("mozilla.org", "mozilla.org")"#, "true"),
+        (r#"dnsDomainIs("mozilla.org", ".mozilla.org")"#, "false"),
+        // a plain suffix comparison, as in Mozilla
+        (r#"dnsDomainIs("notmozilla.org", "mozilla.org")"#, "true"),
+        (r#"dnsDomainIs("www.mozilla.org", "")"#, "true"),
+    ]);
+}
+
+#[test]
+fn local_host_or_domain_is() {
+    assert_eval(&[
+        (
+            r#"localHostOrDomainIs("www.mozilla.org", "www.mozilla.org")"#,
+            "true",
+        ),
+        (r#"localHostOrDomainIs("www", "www.mozilla.org")"#, "true"),
+        // a partially qualified host matches the start of the FQDN
+        (
+            r#"localHostOrDomainIs("www.mozilla", "www.mozilla.org")"#,
+            "true",
+        ),
+        (
+            r#"localHostOrDomainIs("www.google.com", "www.mozilla.org")"#,
+            "false",
+        ),
+        (
+            r#"localHostOrDomainIs("home.mozilla.org", "www.mozilla.org")"#,
+            "false",
+        ),
+        (r#"localHostOrDomainIs("ww", "www.mozilla.org")"#, "false"),
+    ]);
+}
+
+#[test]
+fn dns_domain_levels() {
+    assert_eval(&[
+        (r#"dnsDomainLevels("www")"#, "0"),
+        (r#"dnsDomainLevels("mozilla.org")"#, "1"),
+        (r#"dnsDomainLevels("www.mozilla.org")"#, "2"),
+        // the trailing dot of a fully qualified name counts as a level
+        (r#"dnsDomainLevels("www.mozilla.org.")"#, "3"),
+        (r#"dnsDomainLevels("")"#, "0"),
+    ]);
+}
+
+#[test]
+fn is_resolvable_and_dns_resolve() {
+    assert_eval(&[
+        (r#"isResolvable("www.mozilla.org")"#, "true"),
+        (r#"isResolvable("nowhere.invalid")"#, "false"),
+        (r#"dnsResolve("www.mozilla.org")"#, "198.95.249.79"),
+        // IPv4 is preferred when a name has both families
+        (r#"dnsResolve("dual.example")"#, "10.1.2.3"),
+        (r#"dnsResolve("nowhere.invalid")"#, "null"),
+        (r#"dnsResolve("10.0.0.1")"#, "10.0.0.1"),
+    ]);
+}
+
+#[test]
+fn my_ip_address() {
+    assert_eval(&[
+        ("myIpAddress()", "192.168.1.20"),
+        ("myIpAddressEx()", "192.168.1.20;2001:db8::20"),
+    ]);
+}
+
+#[test]
+fn is_in_net() {
+    assert_eval(&[
+        (
+            r#"isInNet("198.95.249.79", "198.95.249.79", "255.255.255.255")"#,
+            "true",
+        ),
+        (
+            r#"isInNet("198.95.6.8", "198.95.0.0", "255.255.0.0")"#,
+            "true",
+        ),
+        (
+            r#"isInNet("198.96.6.8", "198.95.0.0", "255.255.0.0")"#,
+            "false",
+        ),
+        // host names are resolved first
+        (
+            r#"isInNet("www.mozilla.org", "198.95.0.0", "255.255.0.0")"#,
+            "true",
+        ),
+        (
+            r#"isInNet("nowhere.invalid", "0.0.0.0", "0.0.0.0")"#,
+            "false",
+        ),
+        (
+            r#"isInNet("dual.example", "2001:db8:1::", "ffff:ffff:ffff::")"#,
+            "true",
+        ),
+        (r#"isInNetEx("dual.example", "2001:db8::/32")"#, "true"),
+        (r#"isInNetEx("198.95.6.8", "198.95.0.0/16")"#, "true"),
+    ]);
+}
+
+#[test]
+fn convert_addr() {
+    assert_eval(&[
+        (r#"convert_addr("0.0.0.1")"#, "1"),
+        (r#"convert_addr("10.0.0.1")"#, "167772161"),
+        // the result is a signed 32-bit integer, as in Mozilla
+        (r#"convert_addr("192.168.0.1")"#, "-1062731775"),
+        (r#"convert_addr("255.255.255.255")"#, "-1"),
+        (r#"convert_addr("1.2")"#, "16908288"),
+        (r#"convert_addr("x.y.z.w")"#, "0"),
+        (
+            r#"(convert_addr("198.95.6.8") & convert_addr("255.255.0.0")) == convert_addr("198.95.0.0")"#,
+            "true",
+        ),
+    ]);
+}
+
+#[test]
+fn sh_exp_match() {
+    assert_eval(&[
+        (
+            r#"shExpMatch("http://home.netscape.com/people/ari/index.html", "*/ari/*")"#,
+            "true",
+        ),
+        (
+            r#"shExpMatch("http://home.netscape.com/people/montulli/index.html", "*/ari/*")"#,
+            "false",
+        ),
+        (r#"shExpMatch("www.mozilla.org", "*.mozilla.org")"#, "true"),
+        (r#"shExpMatch("mozilla.org", "*.mozilla.org")"#, "false"),
+        (r#"shExpMatch("a.b", "?.?")"#, "true"),
+        (r#"shExpMatch("a+b", "a+b")"#, "true"),
+        (r#"shExpMatch("host1", "host[0-9]")"#, "true"),
+    ]);
+}
+
+#[test]
+fn weekday_range() {
+    assert_eval(&[
+        (r#"weekdayRange("SAT")"#, "true"),
+        (r#"weekdayRange("MON", "FRI")"#, "false"),
+        (r#"weekdayRange("FRI", "MON")"#, "true"),
+        (r#"weekdayRange("SAT", "GMT")"#, "true"),
+    ]);
+}
+
+#[test]
+fn date_range() {
+    assert_eval(&[
+        ("dateRange(17)", "true"),
+        (r#"dateRange("OCT")"#, "true"),
+        ("dateRange(2026)", "true"),
+        ("dateRange(1, 15)", "false"),
+        (r#"dateRange(17, "OCT", 2026, 17, "OCT", 2026)"#, "true"),
+        (r#"dateRange("DEC", "JAN")"#, "false"),
+    ]);
+}
+
+#[test]
+fn time_range() {
+    assert_eval(&[
+        ("timeRange(14)", "true"),
+        ("timeRange(12)", "false"),
+        (r#"timeRange(12, "GMT")"#, "true"),
+        ("timeRange(9, 17)", "true"),
+        ("timeRange(9, 14)", "true"),
+        ("timeRange(14, 15)", "true"),
+        ("timeRange(14, 14)", "true"),
+        (r#"timeRange(9, 12, "GMT")"#, "true"),
+        (r#"timeRange(12, 13, "GMT")"#, "true"),
+        ("timeRange(22, 6)", "false"),
+        ("timeRange(14, 30, 14, 31)", "true"),
+        ("timeRange(14, 30, 10, 14, 30, 20)", "true"),
+        ("timeRange(14, 30, 16, 14, 30, 20)", "false"),
+    ]);
+}
+
+#[test]
+fn alert_does_not_interrupt_the_script() {
+    assert_eval(&[
+        (r#"alert("resolving " + host)"#, "undefined"),
+        ("alert()", "undefined"),
+    ]);
+}
@@ -0,0 +1,98 @@
+// tests/pac_limits.rs
+//! Execution limits for untrusted PAC scripts.
+
+use proxyparser::pac::{PacEngine, PacError, PacLimit, PacLimits, PacOptions};
+use std::time::{Duration, Instant};
+
+fn options(timeout: Duration) -> PacOptions {
+    PacOptions {
+        limits: PacLimits {
+            timeout,
+            ..PacLimits::default()
+        },
+        ..PacOptions::default()
+    }
+}
+
+fn limit(err: &(dyn std::error::Error + 'static)) -> Option<PacLimit> {
+    match err.downcast_ref::<PacError>()? {
+        PacError::LimitExceeded { limit, .. } => Some(*limit),
+        _ => None,
+    }
+}
+
+// boa 按调用帧计数循环次数：把循环分散到多次函数调用里就不会触发 loop_iteration_limit
+const NESTED_LOOPS: &str = r#"
+    function f() { var n = 0; for (var i = 0; i < 900000; i++) { n += i; } return n; }
+    function spin() { var total = 0; for (var j = 0; j < 40; j++) { total += f(); } return total; }
+"#;
+
+#[test]
+fn timeout_stops_waiting_for_find_proxy() {
+    let script = format!(
+        "{}\nfunction FindProxyForURL(url, host) {{ spin(); return 'DIRECT'; }}",
+        NESTED_LOOPS
+    );
+    let mut engine = PacEngine::with_options(&script, options(Duration::from_millis(100))).unwrap();
+
+    let started = Instant::now();
+    let err = engine.find_proxy_raw("http://example.com/").unwrap_err();
+    assert_eq!(limit(err.as_ref()), Some(PacLimit::Timeout), "{}", err);
+    assert!(
+        started.elapsed() < Duration::from_secs(2),
+        "{:?}",
+        started.elapsed()
+    );
+
+    // 超时后引擎不再接受新的查询
+    let started = Instant::now();
+    let err = engine.find_proxy_raw("http://example.com/").unwrap_err();
+    assert_eq!(limit(err.as_ref()), Some(PacLimit::Timeout), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn timeout_stops_waiting_for_evaluation() {
+    let script = format!(
+        "{}\nspin();\nfunction FindProxyForURL(url, host) {{ return 'DIRECT'; }}",
+        NESTED_LOOPS
+    );
+    let started = Instant::now();
+    let err = PacEngine::with_options(&script, options(Duration::from_millis(100)))
+        .err()
+        .unwrap();
+    assert_eq!(limit(err.as_ref()), Some(PacLimit::Timeout), "{}", err);
+    assert!(
+        started.elapsed() < Duration::from_secs(2),
+        "{:?}",
+        started.elapsed()
+    );
+}
+
+#[test]
+fn limits_inside_the_script_are_reported() {
+    let cases = [
+        ("for (;;) {}", PacLimit::LoopIterations),
+        ("function r() { return r(); } r();", PacLimit::Recursion),
+    ];
+    for (body, expected) in cases {
+        let script = format!("function FindProxyForURL(url, host) {{ {} }}", body);
+        let mut engine =
+            PacEngine::with_options(&script, options(Duration::from_secs(30))).unwrap();
+        let err = engine.find_proxy_raw("http://example.com/").unwrap_err();
+        assert_eq!(limit(err.as_ref()), Some(expected), "{}", body);
+    }
+}
+
+#[test]
+fn engine_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<PacEngine>();
+
+    let mut engine =
+        PacEngine::new("function FindProxyForURL(url, host) { return 'DIRECT'; }").unwrap();
+    let result = std::thread::spawn(move || engine.find_proxy_raw("http://example.com/").unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(result, "DIRECT");
+}